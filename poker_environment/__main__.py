@@ -20,8 +20,8 @@ def add_to_dict(d, k, v):
     return add_to_dict_helper(d, k, v, 0)
 
 
-def main_loop_no_tui(agents: dict[str, Callable[[poker_environment.PyPokerEnvironment], str]], delay):
-    e = Game(agents)
+def main_loop_no_tui(agents: dict[str, Callable[[poker_environment.PyPokerEnvironment], str]], delay, hand_history=None):
+    e = Game(agents, hand_history)
     while not e.is_finished():
         e.advance()
         print(e)
@@ -29,7 +29,7 @@ def main_loop_no_tui(agents: dict[str, Callable[[poker_environment.PyPokerEnviro
 
 
 
-def main_loop_tui(agents: dict[str, Callable[[poker_environment.PyPokerEnvironment], str]], delay):
+def main_loop_tui(agents: dict[str, Callable[[poker_environment.PyPokerEnvironment], str]], delay, hand_history=None):
     import curses
     import curses.textpad
 
@@ -146,15 +146,15 @@ def main_loop_tui(agents: dict[str, Callable[[poker_environment.PyPokerEnvironme
         win.addstr(uly + 2, ulx, "HAND: " + " ".join(unicodify_card(c) for c in player.hand))
 
         # Player Actions
-        if action is not None and action.startswith("RAISE"):
+        if action is not None and action.upper().startswith(("RAISE", "BET", "ALL")):
             win.addstr(uly, 33 + ulx, "> RAISE", curses.A_BOLD)
         else:
             win.addstr(uly, 35 + ulx, "RAISE")
-        if action is not None and action.startswith("CALL"):
+        if action is not None and action.upper().startswith(("CALL", "CHECK")):
             win.addstr(uly + 1, 34 + ulx, "> CALL", curses.A_BOLD)
         else:
             win.addstr(uly + 1, 36 + ulx, "CALL")
-        if action is not None and action.startswith("FOLD"):
+        if action is not None and action.upper().startswith("FOLD"):
             win.addstr(uly + 2, 34 + ulx, "> FOLD", curses.A_BOLD)
         else:
             win.addstr(uly + 2, 36 + ulx, "FOLD")
@@ -162,7 +162,7 @@ def main_loop_tui(agents: dict[str, Callable[[poker_environment.PyPokerEnvironme
     def draw_table_details(win, uly, ulx, env: poker_environment.PyPokerEnvironment):
         win.addstr(uly, ulx, f"// POT: {env.pot} // CUR.BET: {env.expected_bet} // MIN BET: {env.minimum_bet} //")
 
-    e = Game(agents)
+    e = Game(agents, hand_history)
 
     def curse_runner(stdscr):
         stdscr.clear()
@@ -197,10 +197,18 @@ def main_loop_tui(agents: dict[str, Callable[[poker_environment.PyPokerEnvironme
                 # PlayerWindow
                 curses.textpad.rectangle(pad, 10, 0, 14, 41)
                 draw_player(pad, 11, 1, environment.current_player, action)
-                match action.split(" "):
+                match action.upper().split():
+                    case ["RAISE", "TO", amount]:
+                        pad.addstr(15, 1, f"{environment.current_player.player_id} raised to {amount}", curses.A_BOLD)
                     case ["RAISE", amount]:
                         pad.addstr(15, 1, f"{environment.current_player.player_id} raised by {amount}", curses.A_BOLD)
+                    case ["BET", amount]:
+                        pad.addstr(15, 1, f"{environment.current_player.player_id} bet {amount}", curses.A_BOLD)
+                    case ["ALLIN"] | ["ALL_IN"] | ["ALL-IN"] | ["ALL", "IN"]:
+                        pad.addstr(15, 1, f"{environment.current_player.player_id} went all in!", curses.A_BOLD)
                     case ["CALL"]:
+                        pad.addstr(15, 1, f"{environment.current_player.player_id} called.", curses.A_BOLD)
+                    case ["CHECK"]:
                         pad.addstr(15, 1, f"{environment.current_player.player_id} checked.", curses.A_BOLD)
                     case ["FOLD"]:
                         pad.addstr(15, 1, f"{environment.current_player.player_id} folded!", curses.A_BOLD)
@@ -218,7 +226,7 @@ def main_loop_tui(agents: dict[str, Callable[[poker_environment.PyPokerEnvironme
         print(f"{p}: {i}")
 
 
-def main(*bot_path_list: Path, delay=0, starting_balance=1000, no_tui = False):
+def main(*bot_path_list: Path, delay=0, starting_balance=1000, no_tui = False, hand_history=None):
     agent_list = {}
     for bot_path in bot_path_list:
         full_path = Path(bot_path)
@@ -229,13 +237,13 @@ def main(*bot_path_list: Path, delay=0, starting_balance=1000, no_tui = False):
         add_to_dict(agent_list, full_path.stem, (agent, starting_balance))
 
     if no_tui:
-        main_loop_no_tui(agent_list, delay)
+        main_loop_no_tui(agent_list, delay, hand_history)
     else:
         try:
-            main_loop_tui(agent_list, delay)
+            main_loop_tui(agent_list, delay, hand_history)
         except ImportError:
             print("Could not import curses on this system, falling back to no_tui")
-            main_loop_no_tui(agent_list, delay)
+            main_loop_no_tui(agent_list, delay, hand_history)
 
 
 if __name__ == "__main__":
@@ -2,11 +2,31 @@ def call():
     return "CALL"
 
 
+def check():
+    return "CHECK"
+
+
 def fold():
     return "FOLD"
 
 
+def all_in():
+    return "ALLIN"
+
+
 def raise_by(number: int):
     if number < 0:
         raise ValueError("Raise amount must be a valid unsigned int")
-    return f"RAISE {number}"
\ No newline at end of file
+    return f"RAISE {number}"
+
+
+def raise_to(total: int):
+    if total < 0:
+        raise ValueError("Raise total must be a valid unsigned int")
+    return f"RAISE TO {total}"
+
+
+def bet(total: int):
+    if total < 0:
+        raise ValueError("Bet total must be a valid unsigned int")
+    return f"BET {total}"
\ No newline at end of file
@@ -0,0 +1,148 @@
+import random
+from typing import Callable
+
+import gymnasium
+import numpy as np
+from gymnasium import spaces
+
+from poker_environment import ACTION_MASK_KINDS, OBSERVATION_SIZE, PyPokerEnvironment, PyPokerGame, PyPokerPlayerInfo, \
+    bet, raise_to
+
+Policy = Callable[[PyPokerEnvironment], str]
+
+
+def call_policy(environment: PyPokerEnvironment) -> str:
+    return "CHECK" if environment.action_mask[ACTION_MASK_KINDS.index("CHECK")] else "CALL"
+
+
+def random_policy(seed: int | None = None) -> Policy:
+    """A policy taking a random legal action, betting or raising a random legal amount."""
+    rng = random.Random(seed)
+
+    def policy(environment: PyPokerEnvironment) -> str:
+        legal_action = rng.choice(environment.legal_actions)
+        if legal_action.action == "BET":
+            return bet(rng.randint(legal_action.minimum, legal_action.maximum))
+        if legal_action.action == "RAISE":
+            return raise_to(rng.randint(legal_action.minimum, legal_action.maximum))
+        return legal_action.action
+
+    return policy
+
+
+def decode_action(environment: PyPokerEnvironment, action: int, bet_fraction: float | None) -> str:
+    """The action string for an index into `ACTION_MASK_KINDS`, betting or raising to `bet_fraction` of the pot."""
+    kind = ACTION_MASK_KINDS[action]
+    if kind not in ("BET", "RAISE"):
+        return kind
+
+    legal_action = next((x for x in environment.legal_actions if x.action == kind), None)
+    if legal_action is None:
+        # Left to the table's illegal action policy
+        return bet(0) if kind == "BET" else raise_to(0)
+    total = legal_action.minimum
+    if bet_fraction is not None:
+        target = round(environment.street_bet + bet_fraction * environment.pot)
+        total = min(max(target, legal_action.minimum), legal_action.maximum)
+    return bet(total) if kind == "BET" else raise_to(total)
+
+
+class PokerEnv(gymnasium.Env):
+    """
+    One hand of poker per episode, with the agent in seat 0 and `opponents` playing the other seats.
+
+    Observations are `PyPokerEnvironment.encode`, laid out as in `OBSERVATION_LAYOUT`.
+    Actions are the kinds in `ACTION_MASK_KINDS`. Bets and raises go to `bet_fraction` of the pot, or the
+    smallest legal amount when it is `None`. The reward is the agent's chip delta once the hand is over.
+    Hands the agent never has to act in, such as walks, are already over at reset, with `info["hand_finished"]`
+    set and an empty observation. The first step then ends the episode with the chip delta, whatever the action.
+    """
+    metadata = {"render_modes": ["ansi"]}
+
+    def __init__(self, opponents: list[Policy] | None = None, stack: int = 200, blinds: tuple[int, int] = (1, 2),
+                 betting_structure: str = "no-limit", bet_fraction: float | None = None,
+                 illegal_action_policy: str = "fold", render_mode: str | None = None):
+        self.opponents = opponents if opponents is not None else [call_policy]
+        self.player_ids = ["agent"] + [f"opponent_{i + 1}" for i in range(len(self.opponents))]
+        self.stack = stack
+        self.blinds = blinds
+        self.betting_structure = betting_structure
+        self.bet_fraction = bet_fraction
+        self.illegal_action_policy = illegal_action_policy
+        self.render_mode = render_mode
+
+        self.observation_space = spaces.Box(0.0, 1.0, shape=(OBSERVATION_SIZE,), dtype=np.float32)
+        self.action_space = spaces.Discrete(len(ACTION_MASK_KINDS))
+
+        self._game: PyPokerGame | None = None
+        self._environment: PyPokerEnvironment | None = None
+        self._hands = 0
+
+    def reset(self, *, seed: int | None = None, options: dict | None = None):
+        super().reset(seed=seed)
+        # Seeding starts the episodes over, with the button back in seat 0
+        if seed is not None:
+            self._hands = 0
+
+        # The button moves round the table from one episode to the next
+        self._game = PyPokerGame(
+            [PyPokerPlayerInfo(player_id, self.stack) for player_id in self.player_ids],
+            self.blinds[0],
+            int(self.np_random.integers(2 ** 63 - 1)),
+            button=self._hands % len(self.player_ids),
+            illegal_action_policy=self.illegal_action_policy,
+            betting_structure=self.betting_structure,
+            blinds=self.blinds
+        )
+        self._hands += 1
+        self._environment = None
+        if self._play_opponents():
+            return self._observation(), self._info()
+        return np.zeros(OBSERVATION_SIZE, dtype=np.float32), self._info()
+
+    def step(self, action: int):
+        if self._game is None:
+            raise RuntimeError("Call reset before step")
+
+        # A hand that was over before the agent acted ends on this step
+        if self._environment is not None:
+            self._game.advance(decode_action(self._environment, int(action), self.bet_fraction))
+            if self._play_opponents():
+                return self._observation(), 0.0, False, False, self._info()
+
+        deltas = dict(self._game.get_chip_deltas())
+        self._game = None
+        self._environment = None
+        return np.zeros(OBSERVATION_SIZE, dtype=np.float32), float(deltas["agent"]), True, False, self._info()
+
+    def action_masks(self) -> np.ndarray:
+        if self._environment is None:
+            return np.zeros(len(ACTION_MASK_KINDS), dtype=bool)
+        return np.array(self._environment.action_mask, dtype=bool)
+
+    def render(self):
+        if self.render_mode != "ansi" or self._environment is None:
+            return None
+
+        environment = self._environment
+        return (f"Board: {' '.join(x.notation for x in environment.board)}\n"
+                f"Hand: {' '.join(x.notation for x in environment.current_player.cards)}\n"
+                f"Pot: {environment.pot}, stack: {environment.current_player.remaining_balance}")
+
+    # Let the opponents act until the agent has a decision, returning whether it has one
+    def _play_opponents(self) -> bool:
+        while not self._game.is_finished():
+            environment = self._game.get_environment()
+            if environment.current_player.player_id == "agent":
+                self._environment = environment
+                return True
+            opponent = self.opponents[self.player_ids.index(environment.current_player.player_id) - 1]
+            self._game.advance(opponent(environment))
+
+        return False
+
+    def _observation(self) -> np.ndarray:
+        return self._environment.encode()
+
+    def _info(self) -> dict:
+        return {"action_mask": self.action_masks(), "hand_finished": self._environment is None}
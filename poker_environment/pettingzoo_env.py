@@ -0,0 +1,130 @@
+import numpy as np
+from gymnasium import spaces
+from pettingzoo import AECEnv
+
+from poker_environment import ACTION_MASK_KINDS, OBSERVATION_SIZE, PyPokerEnvironment, PyPokerPlayerInfo, PyPokerSession
+from poker_environment.gym_env import decode_action
+
+
+class PokerAECEnv(AECEnv):
+    """
+    A tournament where every seat is an agent, played hand after hand until one agent holds every chip.
+
+    Agents that fold sit out the rest of the hand, and agents that bust are terminated. Each agent's reward
+    is its chip delta at the end of every hand. Actions and observations match `PokerEnv`, with the
+    observation and action mask given together as a dictionary.
+    """
+    metadata = {"render_modes": ["ansi"], "name": "poker_v0", "is_parallelizable": False}
+
+    def __init__(self, n_players: int = 2, stack: int = 200, starting_bet: int = 1, hands_per_level: int = 10,
+                 betting_structure: str = "no-limit", bet_fraction: float | None = None,
+                 max_hands: int | None = None, render_mode: str | None = None):
+        super().__init__()
+        self.possible_agents = [f"player_{i}" for i in range(n_players)]
+        self.stack = stack
+        self.starting_bet = starting_bet
+        self.hands_per_level = hands_per_level
+        self.betting_structure = betting_structure
+        self.bet_fraction = bet_fraction
+        self.max_hands = max_hands
+        self.render_mode = render_mode
+
+        self._observation_space = spaces.Dict({
+            "observation": spaces.Box(0.0, 1.0, shape=(OBSERVATION_SIZE,), dtype=np.float32),
+            "action_mask": spaces.Box(0, 1, shape=(len(ACTION_MASK_KINDS),), dtype=np.int8)
+        })
+        self._action_space = spaces.Discrete(len(ACTION_MASK_KINDS))
+        self._session: PyPokerSession | None = None
+        self._environment: PyPokerEnvironment | None = None
+        # What each agent saw when it last had to act
+        self._observations: dict[str, dict] = {}
+
+    def observation_space(self, agent: str) -> spaces.Space:
+        return self._observation_space
+
+    def action_space(self, agent: str) -> spaces.Space:
+        return self._action_space
+
+    def reset(self, seed: int | None = None, options: dict | None = None):
+        self._session = PyPokerSession(
+            [PyPokerPlayerInfo(agent, self.stack) for agent in self.possible_agents],
+            seed if seed is not None else int(np.random.default_rng().integers(2 ** 63 - 1)),
+            self.starting_bet,
+            self.hands_per_level,
+            illegal_action_policy="fold",
+            betting_structure=self.betting_structure
+        )
+        self.agents = self.possible_agents[:]
+        self.rewards = {agent: 0.0 for agent in self.agents}
+        self._cumulative_rewards = {agent: 0.0 for agent in self.agents}
+        self.terminations = {agent: False for agent in self.agents}
+        self.truncations = {agent: False for agent in self.agents}
+        self.infos = {agent: {} for agent in self.agents}
+        self._observations = {agent: self._empty_observation() for agent in self.agents}
+
+        self._session.start_hand()
+        self._select_current_player()
+
+    def observe(self, agent: str) -> dict:
+        return self._observations.get(agent, self._empty_observation())
+
+    def step(self, action: int | None):
+        agent = self.agent_selection
+        if self.terminations[agent] or self.truncations[agent]:
+            self._was_dead_step(action)
+            return
+
+        self._cumulative_rewards[agent] = 0
+        self._clear_rewards()
+        if self._session.advance(decode_action(self._environment, int(action), self.bet_fraction)) is not None:
+            self._finish_hand()
+        self._select_current_player()
+        self._accumulate_rewards()
+        self._deads_step_first()
+
+    def render(self):
+        if self.render_mode != "ansi" or self._environment is None:
+            return None
+
+        environment = self._environment
+        return (f"{environment.current_player.player_id} to act\n"
+                f"Board: {' '.join(x.notation for x in environment.board)}\n"
+                f"Hand: {' '.join(x.notation for x in environment.current_player.cards)}\n"
+                f"Pot: {environment.pot}, stack: {environment.current_player.remaining_balance}")
+
+    def close(self):
+        pass
+
+    # Hand out each agent's chip delta, end the tournament for busted agents and deal the next hand
+    def _finish_hand(self):
+        for agent, delta in self._session.get_chip_deltas():
+            self.rewards[agent] = float(delta)
+        for agent in self._session.get_eliminated():
+            if agent in self.terminations:
+                self.terminations[agent] = True
+
+        if self._session.is_finished():
+            self.terminations = {agent: True for agent in self.agents}
+        elif self.max_hands is not None and self._session.hands_played() >= self.max_hands:
+            self.truncations = {agent: True for agent in self.agents}
+        else:
+            self._session.start_hand()
+
+    def _select_current_player(self):
+        if not self._session.hand_in_progress():
+            self._environment = None
+            return
+
+        self._environment = self._session.get_environment()
+        self.agent_selection = self._environment.current_player.player_id
+        self._observations[self.agent_selection] = {
+            "observation": self._environment.encode(),
+            "action_mask": np.array(self._environment.action_mask, dtype=np.int8)
+        }
+
+    @staticmethod
+    def _empty_observation() -> dict:
+        return {
+            "observation": np.zeros(OBSERVATION_SIZE, dtype=np.float32),
+            "action_mask": np.zeros(len(ACTION_MASK_KINDS), dtype=np.int8)
+        }
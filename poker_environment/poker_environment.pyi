@@ -1,18 +1,171 @@
+import numpy as np
+
+ACTION_MASK_KINDS: list[str]
+OBSERVATION_VERSION: int
+OBSERVATION_SIZE: int
+OBSERVATION_LAYOUT: dict[str, tuple[int, int]]
+
+class PyCard:
+    rank: int
+    suit: str
+    index: int
+    notation: str
+    def __init__(self, card: str) -> None: ...
+    @staticmethod
+    def from_index(index: int) -> PyCard: ...
+    def __lt__(self, other: PyCard) -> bool: ...
+    def __le__(self, other: PyCard) -> bool: ...
+    def __gt__(self, other: PyCard) -> bool: ...
+    def __ge__(self, other: PyCard) -> bool: ...
+    def __hash__(self) -> int: ...
+
+class PyHand:
+    category: str
+    rank: int
+    cards: list[PyCard]
+    def __lt__(self, other: PyHand) -> bool: ...
+    def __le__(self, other: PyHand) -> bool: ...
+    def __gt__(self, other: PyHand) -> bool: ...
+    def __ge__(self, other: PyHand) -> bool: ...
+    def __hash__(self) -> int: ...
+
+class PyEquity:
+    win: float
+    tie: float
+    lose: float
+    equity: float
+    samples: int
+    exact: bool
+
+class PyRange:
+    combos: list[tuple[tuple[PyCard, PyCard], float]]
+    def __init__(self, notation: str) -> None: ...
+    def without(self, cards: list[PyCard | str]) -> PyRange: ...
+    def __len__(self) -> int: ...
+
 class PyPokerEnvironment:
     table_cards: list[str]
+    board: list[PyCard]
     current_player: PyPokerDealtPlayer
     player_states: list[PyPokerPlayerVisible]
+    folded_players: list[PyPokerPlayerVisible]
     game_history: list[list[str]]
     pot: int
     minimum_bet: int
     expected_bet: int
+    street_bet: int
+    betting_structure: str
+    minimum_raise: int
+    maximum_raise: int | None
+    raises_left: int | None
+    button: int
+    legal_actions: list[PyPokerLegalAction]
+    action_mask: list[bool]
+    def encode(self) -> np.ndarray: ...
+    def info_set_key(self, suit_isomorphism: bool = False) -> str: ...
+    def info_set_hash(self, suit_isomorphism: bool = False) -> int: ...
+
+class PyPokerLegalAction:
+    action: str
+    minimum: int
+    maximum: int
 
 class PyPokerDealtPlayer:
     player_id: str
+    seat: int
     remaining_balance: int
     committed_balance: int
     hand: list[str]
+    cards: list[PyCard]
 
 class PyPokerPlayerVisible:
+    seat: int
     remaining_balance: int
-    committed_balance: int
\ No newline at end of file
+    committed_balance: int
+
+class PyPokerPot:
+    amount: int
+    eligible_players: list[str]
+    winners: list[tuple[str, int]]
+
+class PyPokerPlayerInfo:
+    player_id: str
+    balance: int
+    def __init__(self, player_id: str, balance: int) -> None: ...
+
+class PyPokerGame:
+    def __init__(self, players: list[PyPokerPlayerInfo], minimum_bet: int, seed: int, button: int = 0,
+                 burn_cards: bool = False, illegal_action_policy: str = "reject", betting_structure: str = "no-limit",
+                 raise_cap: int = 4, blinds: tuple[int, int] | None = None, ante: int = 0,
+                 big_blind_ante: int = 0, straddle: bool = False, deck: list[PyCard | str] | None = None,
+                 hands: list[list[PyCard | str] | None] | None = None,
+                 board: list[PyCard | str] | None = None) -> None: ...
+    def advance(self, action: str) -> None: ...
+    def is_finished(self) -> bool: ...
+    def get_environment(self) -> PyPokerEnvironment: ...
+    def info_set_key(self, seat: int, suit_isomorphism: bool = False) -> str: ...
+    def get_players(self) -> tuple[list[PyPokerDealtPlayer], list[PyPokerPlayerInfo]]: ...
+    def get_chip_deltas(self) -> list[tuple[str, int]]: ...
+    def next_hand(self) -> tuple[list[PyPokerPlayerInfo], int]: ...
+    def get_pots(self) -> list[PyPokerPot]: ...
+    def hand_history(self, hand_id: int = 1, table_name: str = "Poker Environment", hero: int = 0) -> str: ...
+    def to_json(self) -> str: ...
+    @staticmethod
+    def from_json(json: str) -> PyPokerGame: ...
+    def __getstate__(self) -> str: ...
+    def __setstate__(self, state: str) -> None: ...
+
+class PyPokerSession:
+    def __init__(self, players: list[PyPokerPlayerInfo], seed: int, starting_bet: int = 2, hands_per_level: int = 5,
+                 growth: float = 1.5, blind_levels: list[int] | None = None,
+                 illegal_action_policy: str = "reject", betting_structure: str = "no-limit",
+                 raise_cap: int = 4, blinds: tuple[int, int] | None = None, ante: int = 0,
+                 big_blind_ante: int = 0, straddle: bool = False) -> None: ...
+    def is_finished(self) -> bool: ...
+    def hand_in_progress(self) -> bool: ...
+    def start_hand(self) -> None: ...
+    def advance(self, action: str) -> list[PyPokerPot] | None: ...
+    def get_environment(self) -> PyPokerEnvironment: ...
+    def get_players(self) -> list[PyPokerPlayerInfo]: ...
+    def get_eliminated(self) -> list[str]: ...
+    def hands_played(self) -> int: ...
+    def minimum_bet(self) -> int: ...
+    def button(self) -> int: ...
+    def get_chip_deltas(self) -> list[tuple[str, int]] | None: ...
+    def hand_history(self, table_name: str = "Poker Environment", hero: int = 0) -> str | None: ...
+
+class PyPokerReplay:
+    hand_id: str
+    steps: list[tuple[PyPokerEnvironment, str]]
+    divergences: list[str]
+    def game(self) -> PyPokerGame: ...
+
+class PyPokerVecGame:
+    def __init__(self, n_games: int, n_players: int = 2, stack: int = 200, blinds: tuple[int, int] = (1, 2),
+                 seed: int = 0, betting_structure: str = "no-limit", raise_cap: int = 4,
+                 bet_fraction: float | None = None, illegal_action_policy: str = "fold",
+                 parallel: bool = False) -> None: ...
+    def reset(self, seed: int | None = None) -> tuple[np.ndarray, np.ndarray]: ...
+    def step(self, actions: list[int]) -> tuple[np.ndarray, np.ndarray, np.ndarray, np.ndarray]: ...
+    def current_seats(self) -> np.ndarray: ...
+    def get_environment(self, index: int) -> PyPokerEnvironment: ...
+    def __len__(self) -> int: ...
+
+def parse_cards(text: str) -> list[PyCard]: ...
+
+def evaluate(hole: list[PyCard | str], board: list[PyCard | str]) -> PyHand: ...
+
+def equity(hole: list[PyCard | str], board: list[PyCard | str] = [], dead: list[PyCard | str] = [],
+           opponents: int = 1, ranges: list[PyRange | str | list[list[PyCard | str]]] | None = None,
+           samples: int = 10000, seed: int = 0) -> PyEquity: ...
+
+def range_equity(hero: PyRange | str | list[list[PyCard | str]], board: list[PyCard | str] = [],
+                 dead: list[PyCard | str] = [], opponents: int = 1,
+                 ranges: list[PyRange | str | list[list[PyCard | str]]] | None = None, samples: int = 10000,
+                 seed: int = 0) -> PyEquity: ...
+
+def compare_hands(first: list[PyCard | str], second: list[PyCard | str], board: list[PyCard | str]) -> int: ...
+
+def replay_hand_history(text: str, format: str = "pokerstars", seed: int = 0) -> list[PyPokerReplay]: ...
+
+class IllegalActionError(ValueError): ...
\ No newline at end of file
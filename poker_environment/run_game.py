@@ -10,55 +10,55 @@ class Game:
     START_MIN_BET = 2
     NUM_ROUNDS_BEFORE_INC = 5
 
-    def __init__(self, agents: dict[str, [Callable[[],str], int]]):
-        self.players = agents
-        self.current_rust_game: poker_environment.PyPokerGame | None = None
-        self.game_counter = 1
+    def __init__(self, agents: dict[str, [Callable[[],str], int]], hand_history_path: str | None = None):
+        self.hand_history_path = hand_history_path
+        self.agents = {name: agent for name, (agent, _) in agents.items()}
+        self.starting_balances = {name: balance for name, (_, balance) in agents.items()}
+        self.session = poker_environment.PyPokerSession(
+            [poker_environment.PyPokerPlayerInfo(name, balance) for name, balance in self.starting_balances.items()],
+            random.randint(0, sys.maxsize),
+            self.START_MIN_BET,
+            self.NUM_ROUNDS_BEFORE_INC,
+            illegal_action_policy="fold"
+        )
 
     def advance(self) -> None | tuple[poker_environment.PyPokerEnvironment, str]:
-        if self.current_rust_game is None:
-            self.current_rust_game = poker_environment.PyPokerGame(
-                [poker_environment.PyPokerPlayerInfo(name, balance)
-                 for name, (_, balance) in self.players.items()
-                 if balance > 0],
-                max(int(self.game_counter / self.NUM_ROUNDS_BEFORE_INC * 1.50 * self.START_MIN_BET), self.START_MIN_BET), random.randint(0, sys.maxsize)
-            )
-            self.game_counter += 1
+        if not self.session.hand_in_progress():
+            self.session.start_hand()
             return None
-        else:
-            current_environment = self.current_rust_game.get_environment()
-            current_player_name = current_environment.current_player.player_id
-            # noinspection PyBroadException
-            try:
-                with time_limit(20):
-                    action = self.players[current_player_name][0](current_environment)
-            except:
-                action = fold()
 
-            self.current_rust_game.advance(action)
+        current_environment = self.session.get_environment()
+        current_player_name = current_environment.current_player.player_id
+        # noinspection PyBroadException
+        try:
+            with time_limit(20):
+                action = self.agents[current_player_name](current_environment)
+        except:
+            action = fold()
 
-            if self.current_rust_game.is_finished():
-                (actives, folders) = self.current_rust_game.get_players()
-                for ap in actives:
-                    self.players[ap.player_id] = (self.players[ap.player_id][0], ap.remaining_balance)
-                for fp in folders:
-                    self.players[fp.player_id] = (self.players[ap.player_id][0], fp.balance)
-                self.current_rust_game = None
-            return current_environment, action
+        if self.session.advance(action) is not None and self.hand_history_path is not None:
+            with open(self.hand_history_path, "a") as f:
+                f.write(self.session.hand_history() + "\n\n")
+        return current_environment, action
+
+    @property
+    def players(self) -> dict[str, tuple[Callable[[], str], int]]:
+        balances = {p.player_id: p.balance for p in self.session.get_players()}
+        return {name: (agent, balances.get(name, 0)) for name, agent in self.agents.items()}
 
     def is_finished(self) -> bool:
-        return sum(1 for (_, balance) in self.players.values() if balance > 0) == 1
+        return self.session.is_finished()
 
     def __str__(self):
         return '\n'.join(f"{name}: {balance}" for name, (_, balance) in self.players.items())
 
 
 if __name__ == "__main__":
-    e = Game([(f"Binks {i + 1}", lambda x : "CALL", 20) for i in range(6)])
+    e = Game({f"Binks {i + 1}": (lambda x : "CALL", 20) for i in range(6)})
 
     while not e.is_finished():
         e.advance()
         print(e)
         print()
 
-    print("Winner:", next(iter(e.players.keys())))
+    print("Winner:", next(name for name, (_, balance) in e.players.items() if balance > 0))
@@ -2,183 +2,331 @@ use std::cmp::min;
 use itertools::Itertools;
 use rand::Rng;
 use playlist::Playlist;
-use crate::rules::{calculate_best_hand, Card, Deck};
+use crate::rules::{Card, Deck};
+use serde::{Deserialize, Serialize};
 
 pub use player::*;
 pub use environment::*;
 pub use history::*;
 pub use action::*;
+pub use pot::*;
+pub use session::*;
+pub use legality::*;
+pub use config::*;
+pub use structure::*;
+pub use replay::*;
+pub use hand_history::*;
+pub use phh::*;
+pub use observation::*;
+pub use info_set::*;
 
 mod player;
 mod environment;
 mod playlist;
 mod history;
 mod action;
+mod pot;
+mod session;
+mod legality;
+mod config;
+mod structure;
+mod hand_history;
+mod replay;
+mod phh;
+mod observation;
+mod info_set;
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum BettingRound<R: Rng + Sized> {
     PreFlop {
         deck: Deck<R>,
         play_list: Playlist<DealtPlayer>,
         bet: (usize, usize, usize),
-        history: GameHistory
+        street: StreetBetting,
+        history: GameHistory,
+        button: usize,
+        config: TableConfig
     },
     Flop {
         deck: Deck<R>,
         play_list: Playlist<DealtPlayer>,
         bet: (usize, usize, usize),
+        street: StreetBetting,
         table: [Card; 3],
-        history: [GameHistory; 2]
+        history: [GameHistory; 2],
+        button: usize,
+        config: TableConfig
     },
     Turn {
         deck: Deck<R>,
         play_list: Playlist<DealtPlayer>,
         bet: (usize, usize, usize),
+        street: StreetBetting,
         table: [Card; 4],
-        history: [GameHistory; 3]
+        history: [GameHistory; 3],
+        button: usize,
+        config: TableConfig
     },
     River {
         deck: Deck<R>,
         play_list: Playlist<DealtPlayer>,
         bet: (usize, usize, usize),
+        street: StreetBetting,
         table: [Card; 5],
-        history: [GameHistory; 4]
+        history: [GameHistory; 4],
+        button: usize,
+        config: TableConfig
     }
 }
 
 impl <R: Rng + Sized> BettingRound<R> {
 
-    pub fn update_state(mut self, next_player_action: Action) -> GameState<R> {
-        let (BettingRound::PreFlop { play_list, bet: (pot, expected_bet, minimum_bet), history, .. }
-        | BettingRound::Flop { play_list, bet: (pot, expected_bet, minimum_bet), history: [.., history], .. }
-        | BettingRound::Turn { play_list, bet: (pot, expected_bet, minimum_bet), history: [.., history], .. }
-        | BettingRound::River { play_list, bet: (pot, expected_bet, minimum_bet), history: [.., history], .. }) = &mut self;
+    /// Every action the current player may take, with the exact amounts each allows.
+    pub fn legal_actions(&self) -> Vec<LegalAction> {
+        let (BettingRound::PreFlop { play_list, bet: (_, expected_bet, _), .. }
+        | BettingRound::Flop { play_list, bet: (_, expected_bet, _), .. }
+        | BettingRound::Turn { play_list, bet: (_, expected_bet, _), .. }
+        | BettingRound::River { play_list, bet: (_, expected_bet, _), .. }) = self;
 
-        // Perform the players action
-        match next_player_action {
-            Action::Raise(raise_amount) => {
-                let should_reset = play_list.next(move |current_player| {
-                    let (ref  mut player_remaining_balance, ref mut player_bet) = current_player.balance;
-                    // Player cannot raise more than they have.
-                    // Players who wish to stay in should call if they cannot afford in order to win the side pot
-                    // Also force folds on inputs smaller than the minimum bet
-                    // Raise gives a guarantee this is not the last player
-                    if raise_amount + *expected_bet - *player_bet > *player_remaining_balance
-                        || raise_amount < *minimum_bet
-                    {
-                        history.push(ActionHistory(current_player.player_id.clone(), Action::Fold));
-                        false
-                    } else {
-                        *expected_bet += raise_amount;
-                        let balance_change = *expected_bet - *player_bet;
-                        *player_remaining_balance -= balance_change;
-                        *player_bet = *expected_bet;
-                        *pot += balance_change;
-                        current_player.expectation = *pot;
-                        history.push(ActionHistory(current_player.player_id.clone(), next_player_action));
-
-                        true
-                    }
-                });
+        let (remaining_balance, player_bet) = play_list.peek_next().balance;
+        let raise_delta = expected_bet.saturating_sub(player_bet);
+        let call_cost = min(raise_delta, remaining_balance);
+        let mut legal_actions = vec![LegalAction::Fold, if call_cost == 0 { LegalAction::Check } else { LegalAction::Call(call_cost) }];
+
+        // Players who cannot afford the minimum raise can still go all in to win the side pot
+        let limits = self.betting_limits();
+        let all_in_raise = remaining_balance.saturating_sub(raise_delta);
+        let maximum_raise = limits.maximum_raise.map_or(all_in_raise, |x| min(x, all_in_raise));
+        if limits.can_raise() && maximum_raise >= limits.minimum_raise {
+            let street_bet = self.street_bet();
+            let (minimum, maximum) = (street_bet + limits.minimum_raise, street_bet + maximum_raise);
+            legal_actions.push(if street_bet == 0 {
+                LegalAction::Bet { minimum, maximum }
+            } else {
+                LegalAction::Raise { minimum, maximum }
+            });
+        }
+
+        // Going all in must either only call or raise within the limits
+        let all_in_allowed = all_in_raise == 0
+            || limits.can_raise() && limits.maximum_raise.is_none_or(|x| all_in_raise <= x);
+        if remaining_balance > 0 && all_in_allowed {
+            legal_actions.push(LegalAction::AllIn(remaining_balance));
+        }
 
-                if should_reset {
-                    play_list.restart_without_last();
+        legal_actions
+    }
+
+    /// The raises the table's betting structure allows the current player.
+    pub fn betting_limits(&self) -> BettingLimits {
+        let (BettingRound::PreFlop { play_list, bet: (pot, expected_bet, minimum_bet), street, config, .. }
+        | BettingRound::Flop { play_list, bet: (pot, expected_bet, minimum_bet), street, config, .. }
+        | BettingRound::Turn { play_list, bet: (pot, expected_bet, minimum_bet), street, config, .. }
+        | BettingRound::River { play_list, bet: (pot, expected_bet, minimum_bet), street, config, .. }) = self;
+
+        let structure = config.betting_structure;
+        let limits = match structure {
+            BettingStructure::NoLimit => BettingLimits {
+                structure,
+                minimum_raise: street.last_raise,
+                maximum_raise: None,
+                raises_left: None
+            },
+            BettingStructure::PotLimit => {
+                // The pot is raised by its size after the player calls
+                let raise_delta = expected_bet.saturating_sub(play_list.peek_next().balance.1);
+                BettingLimits {
+                    structure,
+                    minimum_raise: street.last_raise,
+                    maximum_raise: Some(pot + raise_delta),
+                    raises_left: None
+                }
+            }
+            BettingStructure::FixedLimit { raise_cap } => {
+                // The small bet is the big blind, and the big bet twice that
+                let (_, big_blind) = config.forced_bets.blinds_for(*minimum_bet);
+                let bet_size = match self {
+                    BettingRound::PreFlop { .. } | BettingRound::Flop { .. } => big_blind,
+                    BettingRound::Turn { .. } | BettingRound::River { .. } => 2 * big_blind
+                };
+                BettingLimits {
+                    structure,
+                    minimum_raise: bet_size,
+                    maximum_raise: Some(bet_size),
+                    raises_left: Some(raise_cap.saturating_sub(street.raises))
                 }
             }
-            Action::Call => {
-                play_list.next(move |current_player| {
-                    let (player_remaining_balance, player_bet) = &mut current_player.balance;
-                    let raise_delta = *expected_bet - *player_bet;
-
-                    let actual_raise = min(raise_delta, *player_remaining_balance);
-                    if raise_delta > 0 {
-                        current_player.expectation = *pot;
+        };
+
+        // An all in short of a full raise does not reopen the betting to players who already acted
+        if street.acted.contains(&play_list.peek_next().seat) {
+            return BettingLimits { raises_left: Some(0), ..limits };
+        }
+        limits
+    }
+
+    /// Check `action` against the legal actions, substituting it if the table policy allows.
+    pub fn validate(&self, action: Action) -> Result<Action, IllegalAction> {
+        let legal_actions = self.legal_actions();
+        // Plain raises are checked as the street total they raise the bet to
+        let street_total = match action {
+            Action::Raise(amount) if self.street_bet() == 0 => Action::Bet(amount),
+            Action::Raise(amount) => Action::RaiseTo(self.street_bet() + amount),
+            action => action
+        };
+        if legal_actions.iter().any(|x| x.allows(street_total)) {
+            return Ok(action);
+        }
+
+        match self.config().illegal_action_policy {
+            IllegalActionPolicy::Reject => Err(IllegalAction {
+                player_id: self.current_player_id().to_string(),
+                action,
+                legal_actions
+            }),
+            IllegalActionPolicy::Fold => Ok(Action::Fold),
+            IllegalActionPolicy::Call => Ok(Action::Call)
+        }
+    }
+
+    /// The bet to match on this street alone, zero until someone bets.
+    pub fn street_bet(&self) -> usize {
+        let (BettingRound::PreFlop { bet: (_, expected_bet, _), street, .. }
+        | BettingRound::Flop { bet: (_, expected_bet, _), street, .. }
+        | BettingRound::Turn { bet: (_, expected_bet, _), street, .. }
+        | BettingRound::River { bet: (_, expected_bet, _), street, .. }) = self;
+
+        expected_bet - street.opening_bet
+    }
+
+    pub fn config(&self) -> &TableConfig {
+        let (BettingRound::PreFlop { config, .. }
+        | BettingRound::Flop { config, .. }
+        | BettingRound::Turn { config, .. }
+        | BettingRound::River { config, .. }) = self;
+
+        config
+    }
+
+    fn current_player_id(&self) -> &str {
+        let (BettingRound::PreFlop { play_list, .. }
+        | BettingRound::Flop { play_list, .. }
+        | BettingRound::Turn { play_list, .. }
+        | BettingRound::River { play_list, .. }) = self;
+
+        &play_list.peek_next().player_id
+    }
+
+    /// Play `next_player_action` for the current player.
+    ///
+    /// Illegal actions are handled by the table's [`IllegalActionPolicy`], leaving the round untouched when rejected.
+    pub fn update_state(self, next_player_action: Action) -> Result<GameState<R>, IllegalAction> {
+        let next_player_action = self.validate(next_player_action)?;
+
+        Ok(self.apply(next_player_action))
+    }
+
+    // Play an action already returned by `validate`, without checking it again
+    pub(crate) fn apply(mut self, next_player_action: Action) -> GameState<R> {
+        let (BettingRound::PreFlop { play_list, bet: (pot, expected_bet, _), street, history, button, .. }
+        | BettingRound::Flop { play_list, bet: (pot, expected_bet, _), street, history: [.., history], button, .. }
+        | BettingRound::Turn { play_list, bet: (pot, expected_bet, _), street, history: [.., history], button, .. }
+        | BettingRound::River { play_list, bet: (pot, expected_bet, _), street, history: [.., history], button, .. }) = &mut self;
+
+        // Perform the players action
+        if let Action::Fold = next_player_action {
+            play_list.next(|current_player| {
+                history.push(ActionHistory(current_player.player_id.clone(), next_player_action));
+                false
+            });
+        } else {
+            let mut raised = false;
+            play_list.next(|current_player| {
+                let (player_remaining_balance, player_bet) = &mut current_player.balance;
+                // What the player has put in this hand once the action is done
+                let target_bet = match next_player_action {
+                    Action::Raise(raise_amount) => *expected_bet + raise_amount,
+                    Action::RaiseTo(total) | Action::Bet(total) => street.opening_bet + total,
+                    Action::AllIn => *player_bet + *player_remaining_balance,
+                    _ => min(*expected_bet, *player_bet + *player_remaining_balance)
+                };
+
+                let balance_change = target_bet - *player_bet;
+                *player_remaining_balance -= balance_change;
+                *player_bet = target_bet;
+                *pot += balance_change;
+
+                // An all in for less than the current bet is only a call
+                raised = target_bet > *expected_bet;
+                if raised {
+                    // Only a full raise sets the size the next one has to match
+                    let raise_amount = target_bet - *expected_bet;
+                    if raise_amount >= street.last_raise {
+                        street.last_raise = raise_amount;
+                        street.raises += 1;
+                        street.acted.clear();
                     }
-                    *player_remaining_balance -= actual_raise;
-                    *player_bet += actual_raise;
-                    *pot += actual_raise;
+                    *expected_bet = target_bet;
+                }
+                street.acted.push(current_player.seat);
 
-                    history.push(ActionHistory(current_player.player_id.clone(), next_player_action));
+                // Calling nothing is recorded as the check it is
+                let recorded_action = match next_player_action {
+                    Action::Call if balance_change == 0 => Action::Check,
+                    action => action
+                };
+                history.push(ActionHistory(current_player.player_id.clone(), recorded_action));
 
-                    true
-                });
-            }
-            Action::Fold => {
-                play_list.next(|current_player| {
-                    history.push(ActionHistory(current_player.player_id.clone(), next_player_action));
-                    false
-                });
+                true
+            });
+
+            // Everyone else gets to respond to the raise
+            if raised {
+                play_list.restart_without_last();
             }
         }
 
         // Then check that the next player isn't the only player
         if play_list.len() == 1 {
             match self {
-                BettingRound::PreFlop{ play_list, bet: (pot, expected_bet, ..), history, .. } => {
-                    let (actives, folded) = play_list.into_lists();
-                    GameState::Finished(
-                        Showdown {
-                            players: (distribute_pot(pot, &Vec::with_capacity(0), actives), folded.into_iter().map(|x| x.into()).collect()),
-                            table: Vec::with_capacity(0),
-                            bet: (pot, expected_bet),
-                            history: vec![history]
-                        }
-                    )
+                BettingRound::PreFlop{ play_list, bet, history, button, config, .. } => {
+                    GameState::Finished(Showdown::new(play_list, bet, Vec::with_capacity(0), vec![history], button, &config))
                 }
-                BettingRound::Flop{ play_list, bet: (pot, expected_bet, ..), table, history, .. } => {
-                    let (actives, folded) = play_list.into_lists();
-                    GameState::Finished(
-                        Showdown {
-                            players: (distribute_pot(pot, &Vec::from(table), actives), folded.into_iter().map(|x| x.into()).collect()),
-                            table: Vec::from(table),
-                            bet: (pot, expected_bet),
-                            history: Vec::from(history)
-                        }
-                    )
+                BettingRound::Flop{ play_list, bet, table, history, button, config, .. } => {
+                    GameState::Finished(Showdown::new(play_list, bet, Vec::from(table), Vec::from(history), button, &config))
                 }
-                BettingRound::Turn{ play_list, bet: (pot, expected_bet, ..), table, history, .. } => {
-                    let (actives, folded) = play_list.into_lists();
-                    GameState::Finished(
-                        Showdown {
-                            players: (distribute_pot(pot, &Vec::from(table), actives), folded.into_iter().map(|x| x.into()).collect()),
-                            table: Vec::from(table),
-                            bet: (pot, expected_bet),
-                            history: Vec::from(history)
-                        }
-                    )
+                BettingRound::Turn{ play_list, bet, table, history, button, config, .. } => {
+                    GameState::Finished(Showdown::new(play_list, bet, Vec::from(table), Vec::from(history), button, &config))
                 }
-                BettingRound::River{ play_list, bet: (pot, expected_bet, _), table, history, .. } => {
-                    let (actives, folded) = play_list.into_lists();
-                    GameState::Finished(
-                        Showdown {
-                            players: (distribute_pot(pot, &Vec::from(table), actives), folded.into_iter().map(|x| x.into()).collect()),
-                            table: Vec::from(table),
-                            bet: (pot, expected_bet),
-                            history: Vec::from(history)
-                        }
-                    )
+                BettingRound::River{ play_list, bet, table, history, button, config, .. } => {
+                    GameState::Finished(Showdown::new(play_list, bet, Vec::from(table), Vec::from(history), button, &config))
                 }
             }
         }
         // Otherwise check if we must proceed to the next round
         else if play_list.is_finished() {
-            play_list.restart();
+            // Every street after the first is opened by the first player left of the button
+            let n_seats = play_list.complete_len();
+            play_list.restart_by_key(|x| (x.seat + n_seats - *button - 1) % n_seats);
 
             match self {
-                BettingRound::PreFlop { mut deck, play_list, bet, history } => {
-                    let abc = deck.draw_n();
+                BettingRound::PreFlop { mut deck, play_list, bet, history, button, config, .. } => {
+                    let abc = deck.deal_street();
                     GameState::BettingRound(
                         BettingRound::Flop {
                             deck,
                             play_list,
                             table: abc,
                             bet,
-                            history: [history, Vec::new()]
+                            street: StreetBetting::open(bet.1, config.forced_bets.blinds_for(bet.2).1),
+                            history: [history, Vec::new()],
+                            button,
+                            config
                         }
                     )
                 }
-                BettingRound::Flop{ mut deck, play_list, bet, table: [a,b,c], history: [h1, h2] } => {
-                    let d = deck.draw();
+                BettingRound::Flop{ mut deck, play_list, bet, table: [a,b,c], history: [h1, h2], button, config, .. } => {
+                    let [d] = deck.deal_street();
                     let play_len = play_list.len();
                     GameState::BettingRound(
                         BettingRound::Turn {
@@ -186,12 +334,15 @@ impl <R: Rng + Sized> BettingRound<R> {
                             play_list,
                             table: [a, b, c, d],
                             bet,
-                            history: [h1,h2,Vec::with_capacity(play_len)]
+                            street: StreetBetting::open(bet.1, config.forced_bets.blinds_for(bet.2).1),
+                            history: [h1,h2,Vec::with_capacity(play_len)],
+                            button,
+                            config
                         }
                     )
                 }
-                BettingRound::Turn{ mut deck, play_list, bet, table: [a,b,c, d], history: [h1, h2, h3] } => {
-                    let e = deck.draw();
+                BettingRound::Turn{ mut deck, play_list, bet, table: [a,b,c, d], history: [h1, h2, h3], button, config, .. } => {
+                    let [e] = deck.deal_street();
                     let play_len = play_list.len();
                     GameState::BettingRound(
                         BettingRound::River {
@@ -199,20 +350,15 @@ impl <R: Rng + Sized> BettingRound<R> {
                             play_list,
                             table: [a, b, c, d, e],
                             bet,
-                            history: [h1,h2,h3,Vec::with_capacity(play_len)]
+                            street: StreetBetting::open(bet.1, config.forced_bets.blinds_for(bet.2).1),
+                            history: [h1,h2,h3,Vec::with_capacity(play_len)],
+                            button,
+                            config
                         }
                     )
                 }
-                BettingRound::River{ play_list, bet: (pot, expected_bet, _), table, history, .. } => {
-                    let (actives, folded) = play_list.into_lists();
-                    GameState::Finished(
-                        Showdown {
-                            players: (distribute_pot(pot, &Vec::from(table), actives), folded.into_iter().map(|x| x.into()).collect()),
-                            table: Vec::from(table),
-                            bet: (pot, expected_bet),
-                            history: Vec::from(history)
-                        }
-                    )
+                BettingRound::River{ play_list, bet, table, history, button, config, .. } => {
+                    GameState::Finished(Showdown::new(play_list, bet, Vec::from(table), Vec::from(history), button, &config))
                 }
             }
         }
@@ -230,7 +376,12 @@ impl <R: Rng + Sized> BettingRound<R> {
             BettingRound::River { history: [h1, h2, h3, h4], table, bet: (pot, expected_bet, minimum_bet), .. } => (vec![h1.clone(),h2.clone(), h3.clone(), h4.clone()], Vec::from(table), *pot, *expected_bet, *minimum_bet)
         };
 
-        let (current_player, player_states): (DealtPlayer, Vec<DealtPlayerVisible>) = {
+        let (BettingRound::PreFlop { button, .. }
+        | BettingRound::Flop { button, .. }
+        | BettingRound::Turn { button, .. }
+        | BettingRound::River { button, .. }) = self;
+
+        let (current_player, player_states, folded_players): (DealtPlayer, Vec<DealtPlayerVisible>, Vec<DealtPlayerVisible>) = {
             let (BettingRound::PreFlop { play_list, .. }
             | BettingRound::Flop { play_list, .. }
             | BettingRound::Turn { play_list, .. }
@@ -239,17 +390,26 @@ impl <R: Rng + Sized> BettingRound<R> {
             let mut x = play_list.1.clone();
             let current_player = x.pop_front()
                 .expect("There must always be at least one player");
-            (current_player, x.into_iter().chain(play_list.0.iter().cloned()).map(|x| x.into()).collect())
+            (
+                current_player,
+                x.into_iter().chain(play_list.0.iter().cloned()).map(|x| x.into()).collect(),
+                play_list.2.iter().cloned().map(|x| x.into()).collect()
+            )
         };
 
         Environment {
             table_cards,
             current_player,
             player_states,
+            folded_players,
             game_history,
             pot,
             minimum_bet,
-            expected_bet
+            expected_bet,
+            street_bet: self.street_bet(),
+            betting_limits: self.betting_limits(),
+            button: *button,
+            legal_actions: self.legal_actions()
         }
     }
 }
@@ -265,80 +425,197 @@ impl <'a, R: Rng + Sized> BettingRound<R> {
     }
 }
 
-#[derive(Clone)]
+// Hands in progress far outnumber finished ones, so boxing the round would only add indirection
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Serialize, Deserialize)]
 pub enum GameState<R: Rng + Sized> {
     BettingRound(BettingRound<R>),
     Finished(Showdown)
 }
 
 impl <R: Rng + Sized> GameState<R> {
-    pub fn new_with_players(rng: R, players: Vec<Player>, minimum_bet: usize) -> Self {
-        let mut deck: Deck<R> = Deck::new_with_rng(rng);
+    pub fn new_with_players(rng: R, players: Vec<Player>, minimum_bet: usize, button: usize) -> Self {
+        Self::new_with_deck(Deck::new_with_rng(rng), players, minimum_bet, button, TableConfig::default())
+    }
+
+    /// Deal `hands` to the seats in order and `board` to come, keeping every other card random.
+    /// Seats past the end of `hands` get a random hand, as if given `None`.
+    pub fn new_with_hands(rng: R, players: Vec<Player>, hands: &[Option<[Card; 2]>], board: &[Card], minimum_bet: usize, button: usize, config: TableConfig) -> Self {
+        assert!(hands.len() <= players.len(), "There can only be a hand for each player");
+
+        let mut hands = hands.to_vec();
+        hands.resize(players.len(), None);
+        Self::new_with_deck(Deck::new_with_rng(rng).with_hands(&hands, board), players, minimum_bet, button, config)
+    }
+
+    /// Deal from a deck holding `cards` on top in order, hole cards by seat then the board, with the rest random.
+    pub fn new_stacked(rng: R, players: Vec<Player>, cards: &[Card], minimum_bet: usize, button: usize, config: TableConfig) -> Self {
+        Self::new_with_deck(Deck::new_stacked(rng, cards), players, minimum_bet, button, config)
+    }
+
+    /// Deal a new hand to `players`, given in seat order, with the dealer button on seat `button`.
+    pub fn new_with_deck(mut deck: Deck<R>, players: Vec<Player>, minimum_bet: usize, button: usize, config: TableConfig) -> Self {
         let n_players = players.len();
+        assert!(button < n_players, "The button must be on one of the seats");
 
-        let dealt_players: Vec<DealtPlayer> = players
+        // Heads up the button posts the small blind and acts first before the flop
+        let (small_blind, big_blind) = if n_players == 2 {
+            (button, (button + 1) % n_players)
+        } else {
+            ((button + 1) % n_players, (button + 2) % n_players)
+        };
+        let straddle = (config.forced_bets.straddle && n_players > 2).then_some((big_blind + 1) % n_players);
+
+        let mut dealt_players: Vec<DealtPlayer> = players
             .into_iter().enumerate()
-            .map(|(i, Player { player_id, balance}) | {
-                let blind = {
-                    if i == 0 {
-                        2
-                    } else if i == n_players - 1 {
-                        1
-                    }
-                    else {
-                        0
-                    }
-                } * minimum_bet;
-                let actual_blind = min(blind, balance);
-                DealtPlayer {
-                    player_id,
-                    hand: deck.draw_n(),
-                    balance: (balance - actual_blind, actual_blind),
-                    expectation: actual_blind
-                }
+            .map(|(seat, Player { player_id, balance}) | DealtPlayer {
+                player_id,
+                seat,
+                hand: deck.draw_n(),
+                balance: (balance, 0)
             })
             .collect();
-        let pot = dealt_players.iter().map(|x| x.balance.1).sum();
-        let mut play_list = Playlist::new(dealt_players);
-        play_list.next(|_| {true});
+
+        let (small_blind_bet, big_blind_bet) = config.forced_bets.blinds_for(minimum_bet);
+        let mut forced_bets: Vec<(usize, ForcedBet, usize)> = (0..n_players)
+            .map(|offset| ((small_blind + offset) % n_players, ForcedBet::Ante, config.forced_bets.ante))
+            .collect();
+        forced_bets.extend([
+            (small_blind, ForcedBet::SmallBlind, small_blind_bet),
+            (big_blind, ForcedBet::BigBlind, big_blind_bet),
+            (big_blind, ForcedBet::BigBlindAnte, config.forced_bets.big_blind_ante)
+        ]);
+        forced_bets.extend(straddle.map(|seat| (seat, ForcedBet::Straddle, 2 * big_blind_bet)));
+
+        let mut pot = 0;
+        let mut history = Vec::with_capacity(n_players + forced_bets.len());
+        for (seat, forced_bet, amount) in forced_bets.into_iter().filter(|x| x.2 > 0) {
+            let player = &mut dealt_players[seat];
+            let posted = min(amount, player.balance.0);
+            player.balance.0 -= posted;
+            // Antes go into the pot without counting towards the player's bet
+            if forced_bet.is_live() {
+                player.balance.1 += posted;
+            }
+            pot += posted;
+            history.push(ActionHistory(player.player_id.clone(), Action::Post(forced_bet, posted)));
+        }
+
+        // The big blind, or the straddle, has the option to raise, so they act last
+        let mut street = StreetBetting::pre_flop(big_blind_bet);
+        let expected_bet = if straddle.is_some() {
+            // The straddle is a full raise, so raising it takes at least its size again
+            street.last_raise = 2 * big_blind_bet;
+            street.raises += 1;
+            2 * big_blind_bet
+        } else {
+            big_blind_bet
+        };
+        dealt_players.rotate_left((straddle.unwrap_or(big_blind) + 1) % n_players);
+        let play_list = Playlist::new(dealt_players);
         GameState::BettingRound(
             BettingRound::PreFlop {
                 play_list,
                 deck,
-                bet: (pot, minimum_bet * 2, minimum_bet),
-                history: Vec::with_capacity(n_players)
+                bet: (pot, expected_bet, small_blind_bet),
+                street,
+                history,
+                button,
+                config
             }
         )
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Showdown {
     pub players: (Vec<DealtPlayer>, Vec<Player>),
     pub bet: (usize, usize),
+    pub pots: Vec<Pot>,
     pub table: Vec<Card>,
-    pub history: Vec<GameHistory>
+    pub history: Vec<GameHistory>,
+    // Player ids in seat order, and the seat holding the button this hand
+    pub seats: Vec<String>,
+    pub button: usize,
+    // The chips and hole cards each seat started the hand with
+    pub stacks: Vec<usize>,
+    pub hands: Vec<[Card; 2]>,
+    pub blinds: (usize, usize),
+    pub betting_structure: BettingStructure
 }
 
-pub fn distribute_pot(pot: usize, table_cards: &Vec<Card>, mut active_players: Vec<DealtPlayer>) -> Vec<DealtPlayer> {
-    let winner_list: Vec<&mut DealtPlayer> = active_players.iter_mut().max_set_by_key(|x| calculate_best_hand(x.hand, table_cards));
+impl Showdown {
+    fn new(play_list: Playlist<DealtPlayer>, (pot, expected_bet, minimum_bet): (usize, usize, usize), table: Vec<Card>, history: Vec<GameHistory>, button: usize, config: &TableConfig) -> Self {
+        let (actives, folded) = play_list.into_lists();
+        let dealt: Vec<&DealtPlayer> = actives.iter()
+            .chain(folded.iter())
+            .sorted_by_key(|x| x.seat)
+            .collect();
+        let seats = dealt.iter().map(|x| x.player_id.clone()).collect();
+        let hands = dealt.iter().map(|x| x.hand).collect();
 
-    let sum_exp: usize = winner_list.iter().map(|x| x.expectation).sum();
+        // Antes are in the pot without being part of anyone's bet
+        let dead_posted = |player_id: &str| history[0].iter()
+            .filter_map(|ActionHistory(id, action)| match action {
+                Action::Post(forced_bet, amount) if id == player_id && !forced_bet.is_live() => Some(*amount),
+                _ => None
+            })
+            .sum::<usize>();
+        let stacks = dealt.iter().map(|x| x.balance.0 + x.balance.1 + dead_posted(&x.player_id)).collect();
+        let dead_money = pot - dealt.iter().map(|x| x.balance.1).sum::<usize>();
+        let (actives, pots) = distribute_pots(&table, actives, &folded, dead_money);
+
+        Showdown {
+            players: (actives, folded.into_iter().map(|x| x.into()).collect()),
+            bet: (pot, expected_bet),
+            pots,
+            table,
+            history,
+            seats,
+            button,
+            stacks,
+            hands,
+            blinds: config.forced_bets.blinds_for(minimum_bet),
+            betting_structure: config.betting_structure
+        }
+    }
 
-    for winner in winner_list {
-        let share = (winner.expectation * pot) / sum_exp;
+    /// The chips each seat finished the hand with, in seat order.
+    pub fn final_stacks(&self) -> Vec<usize> {
+        self.seats.iter()
+            .map(|player_id| self.players.0.iter()
+                .find(|x| x.player_id == *player_id)
+                .map(|x| x.balance.0)
+                .or_else(|| self.players.1.iter().find(|x| x.player_id == *player_id).map(|x| x.balance))
+                .unwrap_or(0))
+            .collect()
+    }
 
-        winner.balance.0 += share;
+    /// The chips each seat won or lost over the hand, in seat order.
+    pub fn chip_deltas(&self) -> Vec<isize> {
+        self.final_stacks().iter()
+            .zip(&self.stacks)
+            .map(|(end, start)| *end as isize - *start as isize)
+            .collect()
     }
 
-    // Reset all the current bets and expectations for all the dealt players
-    let all_players = active_players.into_iter().map(|mut x| {
-        x.balance.1 = 0;
-        x.expectation = 0;
+    /// The players still holding chips in seat order, and the seat the button moves to for the next hand.
+    pub fn next_hand(&self) -> (Vec<Player>, usize) {
+        let mut players: Vec<Player> = self.players.0.iter()
+            .map(|x| x.clone().into())
+            .chain(self.players.1.iter().cloned())
+            .collect();
+        players.sort_by_key(|x| self.seats.iter().position(|id| *id == x.player_id));
 
-        x
-    }).collect();
+        // The button moves on to the next seat still in the game
+        let n_seats = players.len();
+        let next_button = (1..=n_seats)
+            .map(|offset| (self.button + offset) % n_seats)
+            .find(|seat| players[*seat].balance > 0)
+            .unwrap_or(0);
+        let button = players[..next_button].iter().filter(|x| x.balance > 0).count();
+        players.retain(|x| x.balance > 0);
 
-    all_players
+        (players, button)
+    }
 }
-
@@ -1,20 +1,68 @@
 use std::fmt::{Display, Formatter};
 use std::error::Error;
-use crate::game::Action::{Call, Fold};
+use crate::game::Action::{AllIn, Call, Check, Fold};
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Copy, Clone)]
+/// A bet the table makes a player post before the cards are dealt.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ForcedBet {
+    SmallBlind,
+    BigBlind,
+    Ante,
+    // The big blind posting the antes for the whole table
+    BigBlindAnte,
+    Straddle
+}
+
+impl ForcedBet {
+    pub const ALL: [ForcedBet; 5] = [ForcedBet::SmallBlind, ForcedBet::BigBlind, ForcedBet::Ante, ForcedBet::BigBlindAnte, ForcedBet::Straddle];
+
+    /// Whether the chips count towards the player's bet, rather than going into the pot as dead money.
+    pub fn is_live(&self) -> bool {
+        matches!(self, ForcedBet::SmallBlind | ForcedBet::BigBlind | ForcedBet::Straddle)
+    }
+}
+
+impl Display for ForcedBet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ForcedBet::SmallBlind => write!(f, "SMALL_BLIND"),
+            ForcedBet::BigBlind => write!(f, "BIG_BLIND"),
+            ForcedBet::Ante => write!(f, "ANTE"),
+            ForcedBet::BigBlindAnte => write!(f, "BIG_BLIND_ANTE"),
+            ForcedBet::Straddle => write!(f, "STRADDLE")
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub enum Action {
+    // Raise the current bet by the amount
     Raise(usize),
+    // Raise the current bet to a total for this street
+    RaiseTo(usize),
+    // Open the betting on this street with a total
+    Bet(usize),
     Call,
+    Check,
+    // Put in every remaining chip, raising if that is more than the current bet
+    AllIn,
     Fold,
+    // A forced bet, only ever made by the table itself
+    Post(ForcedBet, usize),
 }
 
-impl Action {
-    pub fn to_string(&self) -> String {
+impl Display for Action {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Action::Raise(a) => format!("RAISE {}", a),
-            Call => "CALL".to_string(),
-            Fold => "FOLD".to_string()
+            Action::Raise(a) => write!(f, "RAISE {}", a),
+            Action::RaiseTo(a) => write!(f, "RAISE TO {}", a),
+            Action::Bet(a) => write!(f, "BET {}", a),
+            Call => write!(f, "CALL"),
+            Check => write!(f, "CHECK"),
+            AllIn => write!(f, "ALLIN"),
+            Fold => write!(f, "FOLD"),
+            Action::Post(forced_bet, a) => write!(f, "POST {} {}", forced_bet, a)
         }
     }
 }
@@ -37,22 +85,30 @@ impl Display for ActionParseError {
 impl Error for ActionParseError {
 }
 
+fn parse_amount(amount: &str) -> Result<usize, ActionParseError> {
+    amount.trim().parse::<usize>().map_err(|_| ActionParseError::RaiseAmountError)
+}
+
 impl TryFrom<String> for Action {
     type Error = ActionParseError;
 
     fn try_from(value: String) -> Result<Self, Self::Error> {
         let command_uppercase = value.to_uppercase();
-        let mut command_parts = command_uppercase.split_whitespace();
-        match (command_parts.next(), command_parts.next()) {
-            (Some("CALL"), None) => Ok(Call),
-            (Some("RAISE"), Some(a)) => {
-                if let Ok(raise_amount) = a.trim().parse::<usize>() {
-                    Ok(Action::Raise(raise_amount))
-                } else {
-                    Err(ActionParseError::RaiseAmountError)
-                }
-            },
-            (Some("FOLD"), None) => Ok(Fold),
+        let command_parts: Vec<&str> = command_uppercase.split_whitespace().collect();
+        match command_parts.as_slice() {
+            ["CALL"] => Ok(Call),
+            ["CHECK"] => Ok(Check),
+            ["FOLD"] => Ok(Fold),
+            ["ALLIN"] | ["ALL_IN"] | ["ALL-IN"] | ["ALL", "IN"] => Ok(AllIn),
+            ["RAISE", "TO", a] => parse_amount(a).map(Action::RaiseTo),
+            ["RAISE", a] => parse_amount(a).map(Action::Raise),
+            ["BET", a] => parse_amount(a).map(Action::Bet),
+            ["POST", forced_bet, a] => {
+                let forced_bet = ForcedBet::ALL.into_iter()
+                    .find(|x| x.to_string() == *forced_bet)
+                    .ok_or(ActionParseError::UnrecognisedCommand)?;
+                parse_amount(a).map(|a| Action::Post(forced_bet, a))
+            }
             _ => Err(ActionParseError::UnrecognisedCommand)
         }
     }
@@ -0,0 +1,31 @@
+use crate::game::legality::IllegalActionPolicy;
+use crate::game::structure::BettingStructure;
+use serde::{Deserialize, Serialize};
+
+/// Rules of the table that stay the same from hand to hand.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TableConfig {
+    pub illegal_action_policy: IllegalActionPolicy,
+    pub betting_structure: BettingStructure,
+    pub forced_bets: ForcedBets
+}
+
+/// The chips players are made to put in before the cards are dealt.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ForcedBets {
+    // Small and big blinds, fixed for every hand instead of one and two times the minimum bet
+    pub blinds: Option<(usize, usize)>,
+    // Dead money posted by every player
+    pub ante: usize,
+    // Dead money posted by the big blind alone
+    pub big_blind_ante: usize,
+    // Whether the player left of the big blind straddles for twice the big blind, with three or more players
+    pub straddle: bool
+}
+
+impl ForcedBets {
+    /// The small and big blinds for a hand played at `minimum_bet`.
+    pub fn blinds_for(&self, minimum_bet: usize) -> (usize, usize) {
+        self.blinds.unwrap_or((minimum_bet, 2 * minimum_bet))
+    }
+}
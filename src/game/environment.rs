@@ -1,18 +1,61 @@
 use std::fmt::{Display, Formatter};
 use itertools::Itertools;
 use crate::game::history::GameHistory;
+use crate::game::action::Action;
+use crate::game::legality::{action_mask, ActionKind, ActionMask, LegalAction};
 use crate::game::player::{DealtPlayer, DealtPlayerVisible};
+use crate::game::structure::BettingLimits;
 use crate::rules::Card;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Environment {
     pub table_cards: Vec<Card>,
     pub current_player: DealtPlayer,
     pub player_states: Vec<DealtPlayerVisible>,
+    // Players who have folded this hand
+    #[serde(default)]
+    pub folded_players: Vec<DealtPlayerVisible>,
     pub game_history: Vec<GameHistory>,
     pub pot: usize,
     pub minimum_bet: usize,
-    pub expected_bet: usize
+    pub expected_bet: usize,
+    // The part of the expected bet made on this street
+    pub street_bet: usize,
+    pub betting_limits: BettingLimits,
+    // Seat of the dealer button
+    pub button: usize,
+    pub legal_actions: Vec<LegalAction>
+}
+
+impl Environment {
+    pub fn action_mask(&self) -> ActionMask {
+        action_mask(&self.legal_actions)
+    }
+
+    /// An action of the given kind, betting or raising to `bet_fraction` of the pot on top of the current
+    /// bet, or by the smallest legal amount without one.
+    ///
+    /// Kinds that are not legal give an action the table will reject, or settle by its illegal action policy.
+    pub fn action_of_kind(&self, kind: ActionKind, bet_fraction: Option<f64>) -> Action {
+        let sized = |minimum: usize, maximum: usize| match bet_fraction {
+            Some(fraction) => ((self.street_bet as f64 + fraction * self.pot as f64).round() as usize).clamp(minimum, maximum),
+            None => minimum
+        };
+        let limits = self.legal_actions.iter().find_map(|x| match x {
+            LegalAction::Bet { minimum, maximum } | LegalAction::Raise { minimum, maximum } if x.kind() == kind => Some(sized(*minimum, *maximum)),
+            _ => None
+        });
+
+        match kind {
+            ActionKind::Fold => Action::Fold,
+            ActionKind::Check => Action::Check,
+            ActionKind::Call => Action::Call,
+            ActionKind::Bet => Action::Bet(limits.unwrap_or(0)),
+            ActionKind::Raise => Action::RaiseTo(limits.unwrap_or(0)),
+            ActionKind::AllIn => Action::AllIn
+        }
+    }
 }
 
 impl Display for Environment {
@@ -0,0 +1,622 @@
+use std::fmt::Write;
+use itertools::Itertools;
+use crate::game::{Action, ActionHistory, BettingStructure, ForcedBet, ForcedBets, HandHistoryError, Player, Pot, RecordedHand, Showdown, TableConfig};
+use crate::rules::{calculate_best_hand, parse_cards, Card, CardValue, Hand};
+
+const STREETS: [&str; 4] = ["HOLE CARDS", "FLOP", "TURN", "RIVER"];
+
+// A player and the chips handed back to them
+type Returned = (String, usize);
+
+impl Showdown {
+    /// Render the hand as a PokerStars hand history seen by the player in seat `hero`, whose hole cards are the
+    /// only ones dealt face up. Everyone else's are shown only if they reach a showdown.
+    pub fn to_pokerstars(&self, hand_id: u64, table_name: &str, unix_time: u64, hero: usize) -> String {
+        let mut ledger = Ledger::new(&self.seats, &self.stacks, self.blinds);
+        let mut out = String::new();
+
+        let (small_blind, big_blind) = self.blinds;
+        let game = match self.betting_structure {
+            BettingStructure::NoLimit => format!("Hold'em No Limit ({}/{})", small_blind, big_blind),
+            BettingStructure::PotLimit => format!("Hold'em Pot Limit ({}/{})", small_blind, big_blind),
+            // Limit games are named after their small and big bets
+            BettingStructure::FixedLimit { .. } => format!("Hold'em Limit ({}/{})", big_blind, 2 * big_blind)
+        };
+        let _ = writeln!(out, "PokerStars Hand #{}: {} - {} UTC", hand_id, game, format_time(unix_time));
+        let _ = writeln!(out, "Table '{}' {}-max Seat #{} is the button", table_name, self.seats.len(), self.button + 1);
+        for (seat, (player_id, stack)) in self.seats.iter().zip(&self.stacks).enumerate() {
+            let _ = writeln!(out, "Seat {}: {} ({} in chips)", seat + 1, player_id, stack);
+        }
+
+        for (street, history) in self.history.iter().enumerate() {
+            if street == 0 {
+                for ActionHistory(player_id, action) in history.iter().filter(|x| matches!(x.1, Action::Post(..))) {
+                    if let Some(line) = ledger.play(player_id, *action) {
+                        let _ = writeln!(out, "{}: {}", player_id, line);
+                    }
+                }
+                let _ = writeln!(out, "*** {} ***", STREETS[0]);
+                if let Some((player_id, hand)) = self.seats.iter().zip(&self.hands).nth(hero) {
+                    let _ = writeln!(out, "Dealt to {} [{}]", player_id, format_cards(hand));
+                }
+            } else {
+                ledger.next_street();
+                // The cards dealt this street are shown apart from the ones already out
+                let n_cards = street + 2;
+                let _ = match street {
+                    1 => writeln!(out, "*** {} *** [{}]", STREETS[street], format_cards(&self.table[..n_cards])),
+                    _ => writeln!(out, "*** {} *** [{}] [{}]", STREETS[street], format_cards(&self.table[..n_cards - 1]), format_cards(&self.table[n_cards - 1..n_cards]))
+                };
+            }
+
+            for ActionHistory(player_id, action) in history.iter().filter(|x| !matches!(x.1, Action::Post(..))) {
+                if let Some(line) = ledger.play(player_id, *action) {
+                    let _ = writeln!(out, "{}: {}", player_id, line);
+                }
+            }
+        }
+
+        // Nobody called the last part of the biggest bet, so it goes straight back
+        let (pots, uncalled) = self.pots_without(ledger.uncalled_bet());
+        if let Some((player_id, amount)) = &uncalled {
+            let _ = writeln!(out, "Uncalled bet ({}) returned to {}", amount, player_id);
+        }
+
+        let actives = &self.players.0;
+        let at_showdown = actives.len() > 1;
+        let best_hands: Vec<Option<Hand>> = self.seats.iter()
+            .zip(&self.hands)
+            .map(|(player_id, hand)| (at_showdown && actives.iter().any(|x| x.player_id == *player_id))
+                .then(|| calculate_best_hand(*hand, &self.table)))
+            .collect();
+
+        if at_showdown {
+            let _ = writeln!(out, "*** SHOW DOWN ***");
+            for ((player_id, hand), best_hand) in self.seats.iter().zip(&self.hands).zip(&best_hands) {
+                if let Some(best_hand) = best_hand {
+                    let _ = writeln!(out, "{}: shows [{}] ({})", player_id, format_cards(hand), describe(*best_hand));
+                }
+            }
+        }
+        for (name, pot) in pots.iter() {
+            for (player_id, amount) in pot.winners.iter().filter(|x| x.1 > 0) {
+                let _ = writeln!(out, "{} collected {} from {}", player_id, amount, name);
+            }
+        }
+        if !at_showdown {
+            for player in actives {
+                let _ = writeln!(out, "{}: doesn't show hand", player.player_id);
+            }
+        }
+
+        let _ = writeln!(out, "*** SUMMARY ***");
+        let total: usize = pots.iter().map(|x| x.1.amount).sum();
+        if pots.len() > 1 {
+            let _ = writeln!(out, "Total pot {} {}| Rake 0", total, pots.iter().map(|(name, pot)| format!("{} {}. ", capitalise(name), pot.amount)).join(""));
+        } else {
+            let _ = writeln!(out, "Total pot {} | Rake 0", total);
+        }
+        if !self.table.is_empty() {
+            let _ = writeln!(out, "Board [{}]", format_cards(&self.table));
+        }
+
+        for (seat, ((player_id, hand), best_hand)) in self.seats.iter().zip(&self.hands).zip(&best_hands).enumerate() {
+            let position = [
+                (seat == self.button, " (button)"),
+                (ledger.posted(player_id, ForcedBet::SmallBlind), " (small blind)"),
+                (ledger.posted(player_id, ForcedBet::BigBlind), " (big blind)")
+            ].into_iter().filter(|x| x.0).map(|x| x.1).join("");
+            let won: usize = pots.iter()
+                .flat_map(|x| x.1.winners.iter())
+                .filter(|x| x.0 == *player_id)
+                .map(|x| x.1)
+                .sum();
+
+            let outcome = if let Some(street) = self.folded_on(player_id) {
+                match street {
+                    0 => "folded before Flop".to_string(),
+                    street => format!("folded on the {}", capitalise(STREETS[street]))
+                }
+            } else if let Some(best_hand) = best_hand {
+                if won > 0 {
+                    format!("showed [{}] and won ({}) with {}", format_cards(hand), won, describe(*best_hand))
+                } else {
+                    format!("showed [{}] and lost with {}", format_cards(hand), describe(*best_hand))
+                }
+            } else {
+                format!("collected ({})", won)
+            };
+            let _ = writeln!(out, "Seat {}: {}{} {}", seat + 1, player_id, position, outcome);
+        }
+
+        out
+    }
+
+    fn folded_on(&self, player_id: &str) -> Option<usize> {
+        self.history.iter()
+            .position(|x| x.iter().any(|ActionHistory(id, action)| id == player_id && *action == Action::Fold))
+    }
+
+    // Each pot's name, size and winnings, with the uncalled bet taken back out as far as it went back to the bettor
+    fn pots_without(&self, uncalled: Option<Returned>) -> (Vec<(String, Pot)>, Option<Returned>) {
+        let mut pots: Vec<(String, Pot)> = self.pots.iter()
+            .enumerate()
+            .map(|(i, pot)| {
+                let name = match i {
+                    _ if self.pots.len() == 1 => "pot".to_string(),
+                    0 => "main pot".to_string(),
+                    i => format!("side pot-{}", i)
+                };
+                (name, pot.clone())
+            })
+            .collect();
+
+        let mut returned = None;
+        if let Some((player_id, mut amount)) = uncalled {
+            let mut total_returned = 0;
+            for (_, pot) in pots.iter_mut().rev() {
+                for (_, winnings) in pot.winners.iter_mut().filter(|x| x.0 == player_id) {
+                    let returned = amount.min(*winnings);
+                    *winnings -= returned;
+                    pot.amount -= returned;
+                    amount -= returned;
+                    total_returned += returned;
+                }
+            }
+            returned = (total_returned > 0).then_some((player_id, total_returned));
+        }
+        pots.retain(|x| x.1.amount > 0);
+
+        (pots, returned)
+    }
+}
+
+// Replays the betting to work out what every action cost
+struct Ledger<'a> {
+    seats: &'a [String],
+    remaining: Vec<usize>,
+    // Live chips put in this street and over the whole hand
+    street: Vec<usize>,
+    total: Vec<usize>,
+    // The bet to match this street, which a blind too short to post in full does not lower
+    level: usize,
+    blinds: (usize, usize),
+    posts: Vec<(String, ForcedBet)>
+}
+
+impl<'a> Ledger<'a> {
+    fn new(seats: &'a [String], stacks: &[usize], blinds: (usize, usize)) -> Self {
+        Ledger {
+            seats,
+            remaining: stacks.to_vec(),
+            street: vec![0; seats.len()],
+            total: vec![0; seats.len()],
+            level: 0,
+            blinds,
+            posts: Vec::new()
+        }
+    }
+
+    fn next_street(&mut self) {
+        self.street.fill(0);
+        self.level = 0;
+    }
+
+    fn posted(&self, player_id: &str, forced_bet: ForcedBet) -> bool {
+        self.posts.iter().any(|(id, x)| id == player_id && *x == forced_bet)
+    }
+
+    fn uncalled_bet(&self) -> Option<Returned> {
+        let (first, second) = self.total.iter()
+            .enumerate()
+            .sorted_by_key(|(_, x)| std::cmp::Reverse(**x))
+            .take(2)
+            .collect_tuple()?;
+
+        (first.1 > second.1).then(|| (self.seats[first.0].clone(), first.1 - second.1))
+    }
+
+    fn put_in(&mut self, seat: usize, amount: usize, live: bool) {
+        self.remaining[seat] -= amount;
+        if live {
+            self.street[seat] += amount;
+            self.total[seat] += amount;
+            self.level = self.level.max(self.street[seat]);
+        }
+    }
+
+    // The line describing `action` once its chips are in, if it is worth a line at all
+    fn play(&mut self, player_id: &str, action: Action) -> Option<String> {
+        let seat = self.seats.iter().position(|x| x == player_id).expect("Every action is made by a seated player");
+        // Players who are already all in are still asked to act, but only folding changes anything
+        if self.remaining[seat] == 0 && !matches!(action, Action::Post(..) | Action::Fold) {
+            return None;
+        }
+        let level = self.level;
+        let committed = self.street[seat];
+
+        // What the player has put in this street once the action is done
+        let target = match action {
+            Action::Fold | Action::Check | Action::Post(..) => committed,
+            Action::Call => level.min(committed + self.remaining[seat]),
+            Action::Raise(amount) => level + amount,
+            Action::RaiseTo(total) | Action::Bet(total) => total,
+            Action::AllIn => committed + self.remaining[seat]
+        };
+
+        let line = match action {
+            Action::Fold => return Some("folds".to_string()),
+            Action::Check => return Some("checks".to_string()),
+            Action::Post(forced_bet, amount) => {
+                self.put_in(seat, amount, forced_bet.is_live());
+                self.posts.push((player_id.to_string(), forced_bet));
+                self.level = self.level.max(match forced_bet {
+                    ForcedBet::SmallBlind => self.blinds.0,
+                    ForcedBet::BigBlind => self.blinds.1,
+                    ForcedBet::Straddle => 2 * self.blinds.1,
+                    ForcedBet::Ante | ForcedBet::BigBlindAnte => 0
+                });
+                match forced_bet {
+                    ForcedBet::SmallBlind => format!("posts small blind {}", amount),
+                    ForcedBet::BigBlind => format!("posts big blind {}", amount),
+                    ForcedBet::Ante | ForcedBet::BigBlindAnte => format!("posts the ante {}", amount),
+                    ForcedBet::Straddle => format!("posts straddle {}", amount)
+                }
+            }
+            _ if target <= level => {
+                self.put_in(seat, target - committed, true);
+                if target == committed {
+                    return Some("checks".to_string());
+                }
+                format!("calls {}", target - committed)
+            }
+            _ => {
+                self.put_in(seat, target - committed, true);
+                if level == 0 {
+                    format!("bets {}", target)
+                } else {
+                    format!("raises {} to {}", target - level, target)
+                }
+            }
+        };
+
+        if self.remaining[seat] == 0 {
+            Some(format!("{} and is all-in", line))
+        } else {
+            Some(line)
+        }
+    }
+}
+
+fn format_cards(cards: &[Card]) -> String {
+    cards.iter().map(|x| x.to_standard_notation()).join(" ")
+}
+
+fn capitalise(text: &str) -> String {
+    let lowercase = text.to_lowercase();
+    let mut chars = lowercase.chars();
+    chars.next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+fn value_name(value: CardValue) -> &'static str {
+    match value {
+        CardValue::Two => "Deuce",
+        CardValue::Three => "Three",
+        CardValue::Four => "Four",
+        CardValue::Five => "Five",
+        CardValue::Six => "Six",
+        CardValue::Seven => "Seven",
+        CardValue::Eight => "Eight",
+        CardValue::Nine => "Nine",
+        CardValue::Ten => "Ten",
+        CardValue::Jack => "Jack",
+        CardValue::Queen => "Queen",
+        CardValue::King => "King",
+        CardValue::Ace => "Ace"
+    }
+}
+
+fn value_plural(value: CardValue) -> String {
+    match value {
+        CardValue::Six => "Sixes".to_string(),
+        value => format!("{}s", value_name(value))
+    }
+}
+
+/// How PokerStars names a hand, like "two pair, Aces and Kings".
+fn describe(hand: Hand) -> String {
+    let values = hand.values();
+    match hand {
+        Hand::StraightFlush(_) if values[0] == CardValue::Ace => "a Royal Flush".to_string(),
+        Hand::StraightFlush(_) => format!("a straight flush, {} to {}", value_name(values[4]), value_name(values[0])),
+        Hand::FourOfAKind(_) => format!("four of a kind, {}", value_plural(values[0])),
+        Hand::FullHouse(_) => format!("a full house, {} full of {}", value_plural(values[0]), value_plural(values[3])),
+        Hand::Flush(_) => format!("a flush, {} high", value_name(values[0])),
+        Hand::Straight(_) => format!("a straight, {} to {}", value_name(values[4]), value_name(values[0])),
+        Hand::ThreeOfAKind(_) => format!("three of a kind, {}", value_plural(values[0])),
+        Hand::TwoPair(_) => format!("two pair, {} and {}", value_plural(values[0]), value_plural(values[2])),
+        Hand::Pair(_) => format!("a pair of {}", value_plural(values[0])),
+        Hand::HighCard(_) => format!("high card {}", value_name(values[0]))
+    }
+}
+
+// Seconds since the Unix epoch as "YYYY/MM/DD HH:MM:SS"
+fn format_time(unix_time: u64) -> String {
+    let (days, seconds) = (unix_time / 86400, unix_time % 86400);
+
+    // Civil date from days since 1970-01-01, counting in 400 year eras starting each March
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{}/{:02}/{:02} {:02}:{:02}:{:02}", year, month, day, seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+/// Read every hand in a PokerStars hand history.
+pub fn parse_pokerstars(text: &str) -> Result<Vec<RecordedHand>, HandHistoryError> {
+    let mut hands = Vec::new();
+    let mut lines: Vec<&str> = Vec::new();
+    for line in text.lines().map(|x| x.trim_start_matches('\u{feff}').trim()) {
+        if line.starts_with("PokerStars ") && line.contains("Hand #") && !lines.is_empty() {
+            hands.push(parse_pokerstars_hand(&lines)?);
+            lines.clear();
+        }
+        if !line.is_empty() {
+            lines.push(line);
+        }
+    }
+    if !lines.is_empty() {
+        hands.push(parse_pokerstars_hand(&lines)?);
+    }
+
+    Ok(hands)
+}
+
+fn parse_pokerstars_hand(lines: &[&str]) -> Result<RecordedHand, HandHistoryError> {
+    let malformed = |line: &str| HandHistoryError::Malformed(line.to_string());
+    let header = lines[0];
+    let hand_id = header.split_once("Hand #")
+        .and_then(|(_, rest)| rest.split(':').next())
+        .ok_or_else(|| malformed(header))?
+        .to_string();
+    if !header.contains("Hold'em") {
+        return Err(HandHistoryError::Unsupported(format!("hand #{} is not Hold'em", hand_id)));
+    }
+    let betting_structure = if header.contains("No Limit") {
+        BettingStructure::NoLimit
+    } else if header.contains("Pot Limit") {
+        BettingStructure::PotLimit
+    } else if header.contains("Limit") {
+        BettingStructure::FixedLimit { raise_cap: 4 }
+    } else {
+        return Err(HandHistoryError::Unsupported(format!("the betting structure of hand #{}", hand_id)));
+    };
+
+    // The stakes are the last bracketed pair in the header, and cash games count in cents
+    let stakes = header.match_indices('(')
+        .filter_map(|(i, _)| header[i + 1..].split(')').next())
+        .rfind(|x| x.contains('/'))
+        .ok_or_else(|| malformed(header))?;
+    let cents = stakes.contains(['$', '€', '£']);
+    let chips = |amount: &str| parse_chips(amount, cents).ok_or_else(|| malformed(amount));
+    let (low_stake, high_stake) = stakes.split_once('/').ok_or_else(|| malformed(header))?;
+    let (low_stake, high_stake) = (chips(low_stake)?, chips(high_stake)?);
+
+    let mut seats: Vec<(usize, Player)> = Vec::new();
+    let mut button_seat = None;
+    let mut hands = Vec::new();
+    let mut board = Vec::new();
+    let mut actions = Vec::new();
+    let mut total_pot = None;
+    // Chips each seat put in this street and over the whole hand, and what came back to them
+    let (mut street, mut invested, mut uncalled, mut collected): (Vec<usize>, Vec<usize>, Vec<usize>, Vec<usize>) = Default::default();
+    let mut in_summary = false;
+
+    for line in lines[1..].iter().copied() {
+        let seat_of = |prefix: &str| -> Option<(usize, &str)> {
+            seats.iter()
+                .enumerate()
+                .filter(|(_, (_, player))| line.starts_with(&format!("{}{}", player.player_id, prefix)))
+                .max_by_key(|(_, (_, player))| player.player_id.len())
+                .map(|(seat, (_, player))| (seat, &line[player.player_id.len() + prefix.len()..]))
+        };
+
+        if let Some(rest) = line.strip_prefix("Table ") {
+            let seat_number = rest.split_once("Seat #").and_then(|(_, x)| x.split(' ').next());
+            button_seat = Some(seat_number.and_then(|x| x.parse::<usize>().ok()).ok_or_else(|| malformed(line))?);
+        } else if let Some(rest) = line.strip_prefix("Seat ").filter(|_| !in_summary) {
+            if line.ends_with("is sitting out") {
+                continue;
+            }
+            let (seat_number, rest) = rest.split_once(": ").ok_or_else(|| malformed(line))?;
+            let (player_id, stack) = rest.rsplit_once(" (").ok_or_else(|| malformed(line))?;
+            let stack = stack.split(" in chips").next().ok_or_else(|| malformed(line))?;
+            seats.push((seat_number.parse().map_err(|_| malformed(line))?, Player {
+                player_id: player_id.to_string(),
+                balance: chips(stack)?
+            }));
+            hands.push(None);
+            street.push(0);
+            invested.push(0);
+            uncalled.push(0);
+            collected.push(0);
+        } else if let Some(street_name) = line.strip_prefix("*** ").and_then(|x| x.split(" ***").next()) {
+            match street_name {
+                "HOLE CARDS" | "SHOW DOWN" => (),
+                "FLOP" | "TURN" | "RIVER" => {
+                    street.fill(0);
+                    board = parse_cards(&line.split(['[', ']']).skip(1).step_by(2).join(" "))?;
+                }
+                "SUMMARY" => in_summary = true,
+                _ => return Err(HandHistoryError::Unsupported(format!("\"{}\" in hand #{}", street_name, hand_id)))
+            }
+        } else if let Some(rest) = line.strip_prefix("Dealt to ") {
+            let dealt = seats.iter()
+                .position(|(_, player)| rest.starts_with(&format!("{} [", player.player_id)));
+            if let Some(seat) = dealt {
+                hands[seat] = Some(parse_hand(&rest[seats[seat].1.player_id.len()..])?);
+            }
+        } else if let Some(rest) = line.strip_prefix("Uncalled bet (") {
+            let (amount, player_id) = rest.split_once(") returned to ").ok_or_else(|| malformed(line))?;
+            let seat = seats.iter().position(|(_, x)| x.player_id == player_id).ok_or_else(|| malformed(line))?;
+            uncalled[seat] += chips(amount)?;
+        } else if let Some(rest) = line.strip_prefix("Total pot ") {
+            total_pot = Some(chips(rest.split(' ').next().unwrap_or(rest))?);
+        } else if let Some(rest) = line.strip_prefix("Board ") {
+            board = parse_cards(rest.trim_matches(['[', ']']))?;
+        } else if let Some(rest) = line.strip_prefix("Seat ").filter(|_| in_summary) {
+            // Summaries show the cards of everyone who went to showdown
+            let (_, rest) = rest.split_once(": ").ok_or_else(|| malformed(line))?;
+            let shown = rest.split_once(" showed [").or_else(|| rest.split_once(" mucked ["));
+            if let Some((player_id, cards)) = shown {
+                let player_id = player_id.split(" (").next().unwrap_or(player_id);
+                if let Some(seat) = seats.iter().position(|(_, x)| x.player_id == player_id) {
+                    hands[seat] = Some(parse_hand(&format!("[{}", cards))?);
+                }
+            }
+        } else if let Some((seat, rest)) = seat_of(": ") {
+            let player_id = seats[seat].1.player_id.clone();
+            let rest = rest.trim_end_matches(" and is all-in");
+            let (verb, amount) = rest.split_once(' ').unwrap_or((rest, ""));
+            let action = match verb {
+                "posts" => {
+                    let (forced_bet, amount) = match amount.rsplit_once(' ') {
+                        Some(("small blind", amount)) => (ForcedBet::SmallBlind, amount),
+                        Some(("big blind", amount)) => (ForcedBet::BigBlind, amount),
+                        Some(("the ante", amount)) => (ForcedBet::Ante, amount),
+                        Some(("straddle", amount)) => (ForcedBet::Straddle, amount),
+                        _ => return Err(HandHistoryError::Unsupported(format!("\"{}\" in hand #{}", line, hand_id)))
+                    };
+                    let amount = chips(amount)?;
+                    invested[seat] += amount;
+                    if forced_bet.is_live() {
+                        street[seat] += amount;
+                    }
+                    Action::Post(forced_bet, amount)
+                }
+                "folds" => {
+                    if amount.starts_with('[') {
+                        hands[seat] = Some(parse_hand(amount)?);
+                    }
+                    Action::Fold
+                }
+                "checks" => Action::Check,
+                "calls" => {
+                    let amount = chips(amount)?;
+                    invested[seat] += amount;
+                    street[seat] += amount;
+                    Action::Call
+                }
+                "bets" | "raises" => {
+                    let total = chips(amount.rsplit(' ').next().unwrap_or(amount))?;
+                    invested[seat] += total.saturating_sub(street[seat]);
+                    street[seat] = total;
+                    if verb == "bets" { Action::Bet(total) } else { Action::RaiseTo(total) }
+                }
+                "shows" => {
+                    hands[seat] = Some(parse_hand(amount)?);
+                    continue;
+                }
+                _ => continue
+            };
+            actions.push(ActionHistory(player_id, action));
+        } else if let Some((seat, amount)) = seat_of(" collected ") {
+            collected[seat] += chips(amount.split(' ').next().unwrap_or(amount))?;
+        }
+    }
+
+    let button_seat = button_seat.ok_or_else(|| malformed(header))?;
+    let button = seats.iter()
+        .position(|(seat_number, _)| *seat_number == button_seat)
+        .ok_or_else(|| HandHistoryError::Unsupported(format!("the button is on an empty seat in hand #{}", hand_id)))?;
+
+    // Limit games are named after their small bet, which is the big blind
+    let big_blind = match betting_structure {
+        BettingStructure::FixedLimit { .. } => low_stake,
+        _ => high_stake
+    };
+    let posted = |forced_bet: ForcedBet| actions.iter()
+        .filter_map(|ActionHistory(player_id, action)| match action {
+            Action::Post(x, amount) if *x == forced_bet => Some((player_id.clone(), *amount)),
+            _ => None
+        })
+        .collect::<Vec<_>>();
+    let small_blind = match betting_structure {
+        BettingStructure::FixedLimit { .. } => posted(ForcedBet::SmallBlind).first().map_or(big_blind / 2, |x| x.1),
+        _ => low_stake
+    };
+    let antes = posted(ForcedBet::Ante);
+    let big_blind_player = posted(ForcedBet::BigBlind).first().map(|x| x.0.clone());
+    // A single ante from the big blind is a big blind ante
+    let big_blind_ante = match antes.as_slice() {
+        [(player_id, amount)] if Some(player_id) == big_blind_player.as_ref() => *amount,
+        _ => 0
+    };
+    let ante = if big_blind_ante > 0 { 0 } else { antes.iter().map(|x| x.1).max().unwrap_or(0) };
+    let straddle = !posted(ForcedBet::Straddle).is_empty();
+    if big_blind_ante > 0 {
+        for ActionHistory(_, action) in actions.iter_mut() {
+            if let Action::Post(forced_bet @ ForcedBet::Ante, _) = action {
+                *forced_bet = ForcedBet::BigBlindAnte;
+            }
+        }
+    }
+
+    let final_stacks = seats.iter()
+        .enumerate()
+        .map(|(seat, (_, player))| (player.balance + uncalled[seat] + collected[seat]).saturating_sub(invested[seat]))
+        .collect();
+
+    Ok(RecordedHand {
+        hand_id,
+        seats: seats.into_iter().map(|x| x.1).collect(),
+        button,
+        config: TableConfig {
+            betting_structure,
+            forced_bets: ForcedBets {
+                blinds: Some((small_blind, big_blind)),
+                ante,
+                big_blind_ante,
+                straddle
+            },
+            ..TableConfig::default()
+        },
+        hands,
+        board,
+        actions,
+        total_pot: total_pot.map(|x| x + uncalled.iter().sum::<usize>()),
+        final_stacks: Some(final_stacks)
+    })
+}
+
+// Two hole cards in brackets, like "[Ah Kd]", ignoring anything after them
+fn parse_hand(text: &str) -> Result<[Card; 2], HandHistoryError> {
+    let cards = text.trim_start()
+        .strip_prefix('[')
+        .and_then(|x| x.split(']').next())
+        .ok_or_else(|| HandHistoryError::Malformed(text.to_string()))?;
+
+    parse_cards(cards)?.try_into().map_err(|_| HandHistoryError::Malformed(text.to_string()))
+}
+
+// An amount of chips, or of cents when playing for money, like "1,500" or "$0.25"
+fn parse_chips(amount: &str, cents: bool) -> Option<usize> {
+    let amount: String = amount.chars().filter(|x| x.is_ascii_digit() || *x == '.').collect();
+    match amount.split_once('.') {
+        Some((whole, fraction)) if cents && fraction.len() <= 2 => {
+            let whole = if whole.is_empty() { 0 } else { whole.parse::<usize>().ok()? };
+            Some(100 * whole + format!("{:0<2}", fraction).parse::<usize>().ok()?)
+        }
+        Some(_) => None,
+        None if cents => amount.parse::<usize>().ok().map(|x| 100 * x),
+        None => amount.parse().ok()
+    }
+}
@@ -1,6 +1,7 @@
 use crate::game::action::Action;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct ActionHistory(pub String, pub Action);
 
 pub type GameHistory = Vec<ActionHistory>;
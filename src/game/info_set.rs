@@ -0,0 +1,95 @@
+//! Keys naming what a player knows at a point in a hand, for solvers that keep a table per information set.
+//!
+//! A key reads `hole|board|position|betting`, like `AhKd|Qs7c2d|2|r6c/kb4`:
+//! - the hole cards, highest first, then the flop highest first followed by the turn and the river,
+//! - the seat's position counted from the button,
+//! - every action other than forced bets in short form, with streets split by `/`. Folds are `f`, checks
+//!   `k` and calls `c`, while bets `b` and raises `r` come with the street total they go to. Raises by an
+//!   amount, raises to a total and all ins are all written by that total, so the same wager always gives
+//!   the same key, and an all in that only calls is a call.
+//!
+//! With suit isomorphism the suits are renamed to give the smallest key, so hands that only differ by suit
+//! share one.
+
+use itertools::Itertools;
+use rand::Rng;
+use crate::game::{Action, ActionHistory, BettingRound, Environment};
+use crate::rules::{Card, CardSuit};
+
+impl Environment {
+    /// The information set of the player to act.
+    pub fn info_set_key(&self, suit_isomorphism: bool) -> String {
+        let position = (self.current_player.seat + self.n_seats() - self.button) % self.n_seats();
+
+        info_set_key(self.current_player.hand, position, self, suit_isomorphism)
+    }
+
+    /// A stable 64-bit hash of [`Environment::info_set_key`].
+    pub fn info_set_hash(&self, suit_isomorphism: bool) -> u64 {
+        info_set_hash(&self.info_set_key(suit_isomorphism))
+    }
+}
+
+impl <R: Rng + Sized> BettingRound<R> {
+    /// The information set of the player in `seat`, whether or not it is their turn, if they were dealt in.
+    pub fn info_set_key(&self, seat: usize, suit_isomorphism: bool) -> Option<String> {
+        let (BettingRound::PreFlop { play_list, button, .. }
+        | BettingRound::Flop { play_list, button, .. }
+        | BettingRound::Turn { play_list, button, .. }
+        | BettingRound::River { play_list, button, .. }) = self;
+
+        let player = play_list.0.iter()
+            .chain(&play_list.1)
+            .chain(&play_list.2)
+            .find(|x| x.seat == seat)?;
+        let n_seats = play_list.complete_len();
+        let environment = self.get_environment();
+
+        Some(info_set_key(player.hand, (seat + n_seats - button) % n_seats, &environment, suit_isomorphism))
+    }
+}
+
+/// The 64-bit FNV-1a hash of a key, which stays the same across platforms and releases.
+pub fn info_set_hash(key: &str) -> u64 {
+    key.bytes().fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+fn info_set_key(hand: [Card; 2], position: usize, environment: &Environment, suit_isomorphism: bool) -> String {
+    let board = &environment.table_cards;
+    let cards = if suit_isomorphism {
+        CardSuit::ALL.into_iter()
+            .permutations(CardSuit::ALL.len())
+            .map(|suits| {
+                let rename = |card: &Card| Card(suits[card.0 as usize], card.1);
+                cards_key(hand.map(|x| rename(&x)), &board.iter().map(rename).collect_vec())
+            })
+            .min()
+            .expect("There is always a way to name the suits")
+    } else {
+        cards_key(hand, board)
+    };
+
+    let totals = environment.street_totals();
+    let betting = (0..environment.game_history.len())
+        .map(|street| totals.iter()
+            .filter(|x| x.0 == street)
+            .map(|(_, ActionHistory(_, action), street_bet, total)| match action {
+                Action::Fold => "f".to_string(),
+                Action::Check => "k".to_string(),
+                _ if total <= street_bet => "c".to_string(),
+                _ if *street_bet == 0 => format!("b{}", total),
+                _ => format!("r{}", total)
+            })
+            .join(""))
+        .join("/");
+
+    format!("{}|{}|{}", cards, position, betting)
+}
+
+// The hole cards and board, with the cards whose order does not matter sorted highest first
+fn cards_key(hand: [Card; 2], board: &[Card]) -> String {
+    let descending = |cards: &[Card]| cards.iter().sorted_by_key(|x| std::cmp::Reverse(x.index())).map(Card::to_standard_notation).join("");
+    let (flop, rest) = board.split_at(board.len().min(3));
+
+    format!("{}|{}{}", descending(&hand), descending(flop), rest.iter().map(Card::to_standard_notation).join(""))
+}
@@ -0,0 +1,128 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use itertools::Itertools;
+use crate::game::action::Action;
+use serde::{Deserialize, Serialize};
+
+/// The kinds of action a player can take, in the order used by action masks.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum ActionKind {
+    Fold,
+    Check,
+    Call,
+    Bet,
+    Raise,
+    AllIn
+}
+
+impl ActionKind {
+    pub const ALL: [ActionKind; 6] = [ActionKind::Fold, ActionKind::Check, ActionKind::Call, ActionKind::Bet, ActionKind::Raise, ActionKind::AllIn];
+}
+
+impl Display for ActionKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ActionKind::Fold => write!(f, "FOLD"),
+            ActionKind::Check => write!(f, "CHECK"),
+            ActionKind::Call => write!(f, "CALL"),
+            ActionKind::Bet => write!(f, "BET"),
+            ActionKind::Raise => write!(f, "RAISE"),
+            ActionKind::AllIn => write!(f, "ALLIN")
+        }
+    }
+}
+
+/// One flag per [`ActionKind`], set when that kind of action is legal.
+pub type ActionMask = [bool; ActionKind::ALL.len()];
+
+pub fn action_mask(legal_actions: &[LegalAction]) -> ActionMask {
+    ActionKind::ALL.map(|kind| legal_actions.iter().any(|x| x.kind() == kind))
+}
+
+/// An action the current player is allowed to take.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum LegalAction {
+    Fold,
+    Check,
+    // Chips needed to match the current bet, capped at the player's remaining balance
+    Call(usize),
+    // Smallest and largest total the first bet of the street can be
+    Bet {
+        minimum: usize,
+        maximum: usize
+    },
+    // Smallest and largest total for this street the current bet can be raised to
+    Raise {
+        minimum: usize,
+        maximum: usize
+    },
+    // Chips the player has left to put in
+    AllIn(usize)
+}
+
+impl LegalAction {
+    pub fn kind(&self) -> ActionKind {
+        match self {
+            LegalAction::Fold => ActionKind::Fold,
+            LegalAction::Check => ActionKind::Check,
+            LegalAction::Call(_) => ActionKind::Call,
+            LegalAction::Bet { .. } => ActionKind::Bet,
+            LegalAction::Raise { .. } => ActionKind::Raise,
+            LegalAction::AllIn(_) => ActionKind::AllIn
+        }
+    }
+
+    /// Whether this allows `action`, with plain raises already turned into street totals.
+    pub fn allows(&self, action: Action) -> bool {
+        match (self, action) {
+            (LegalAction::Fold, Action::Fold)
+            | (LegalAction::Check, Action::Check | Action::Call)
+            | (LegalAction::Call(_), Action::Call)
+            | (LegalAction::AllIn(_), Action::AllIn) => true,
+            (LegalAction::Bet { minimum, maximum }, Action::Bet(total))
+            | (LegalAction::Raise { minimum, maximum }, Action::RaiseTo(total)) => (*minimum..=*maximum).contains(&total),
+            _ => false
+        }
+    }
+}
+
+impl Display for LegalAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LegalAction::Fold => write!(f, "FOLD"),
+            LegalAction::Check => write!(f, "CHECK"),
+            LegalAction::Call(cost) => write!(f, "CALL (costs {})", cost),
+            LegalAction::Bet { minimum, maximum } => write!(f, "BET {}-{}", minimum, maximum),
+            LegalAction::Raise { minimum, maximum } => write!(f, "RAISE TO {}-{}", minimum, maximum),
+            LegalAction::AllIn(chips) => write!(f, "ALLIN ({})", chips)
+        }
+    }
+}
+
+/// What the table does with an action the current player is not allowed to take.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum IllegalActionPolicy {
+    // Refuse the action and leave the game untouched
+    #[default]
+    Reject,
+    // Treat the action as a fold
+    Fold,
+    // Treat the action as a call
+    Call
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IllegalAction {
+    pub player_id: String,
+    pub action: Action,
+    pub legal_actions: Vec<LegalAction>
+}
+
+impl Display for IllegalAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "\"{}\" cannot {}. Legal actions: {}", self.player_id, self.action, self.legal_actions.iter().join(", "))
+    }
+}
+
+impl Error for IllegalAction {
+}
@@ -0,0 +1,211 @@
+//! A fixed-size numeric encoding of an [`Environment`] for neural networks.
+//!
+//! Every value lies between 0 and 1, with chips as fractions of every chip in the hand, antes included.
+//! Seats are counted round the table from the player to act, so the same slot always means the same player
+//! relative to them.
+//!
+//! | Part       | Length | Contents                                                                   |
+//! |------------|--------|----------------------------------------------------------------------------|
+//! | `hole`     | 52     | The player's hole cards, one hot by [`crate::rules::Card::index`]          |
+//! | `board`    | 52     | The board cards, one hot the same way                                      |
+//! | `street`   | 4      | Pre-flop, flop, turn or river, one hot                                     |
+//! | `position` | 10     | Seats from the button to the player, one hot, with the button at 0         |
+//! | `seats`    | 50     | For each of 10 seats from the player: dealt, still in, all in, chips left and chips committed |
+//! | `chips`    | 4      | The pot, the cost to call, the bet on this street and the minimum raise     |
+//! | `legal`    | 6      | The action mask, in the order of [`ActionKind::ALL`]                       |
+//! | `actions`  | 336    | The last 16 actions, newest first, each as its kind one hot (6), street one hot (4), the seat that made it one hot (10) and the street total it brings the player to (1), left at 0 for folds and checks |
+//!
+//! Forced bets are left out of the actions, and seats beyond the tenth share the last slot. Any change to
+//! the layout comes with a new [`OBSERVATION_VERSION`].
+
+use std::collections::HashMap;
+use crate::game::{Action, ActionHistory, ActionKind, Environment, LegalAction};
+
+/// Version of the observation layout, raised whenever it changes
+pub const OBSERVATION_VERSION: u32 = 1;
+
+/// Seats given their own slot
+pub const MAX_SEATS: usize = 10;
+
+/// Most recent actions kept
+pub const MAX_ACTIONS: usize = 16;
+
+// Values describing each action in the sequence
+const ACTION_FEATURES: usize = ActionKind::ALL.len() + 4 + MAX_SEATS + 1;
+
+// Values describing each seat
+const SEAT_FEATURES: usize = 5;
+
+/// The parts of an observation in order, with their lengths
+pub const OBSERVATION_LAYOUT: [(&str, usize); 8] = [
+    ("hole", 52),
+    ("board", 52),
+    ("street", 4),
+    ("position", MAX_SEATS),
+    ("seats", MAX_SEATS * SEAT_FEATURES),
+    ("chips", 4),
+    ("legal", ActionKind::ALL.len()),
+    ("actions", MAX_ACTIONS * ACTION_FEATURES)
+];
+
+/// Length of an encoded observation
+pub const OBSERVATION_SIZE: usize = {
+    let mut size = 0;
+    let mut i = 0;
+    while i < OBSERVATION_LAYOUT.len() {
+        size += OBSERVATION_LAYOUT[i].1;
+        i += 1;
+    }
+    size
+};
+
+// Where each part starts, as the running total of the lengths before it
+const fn offset(part: usize) -> usize {
+    let mut start = 0;
+    let mut i = 0;
+    while i < part {
+        start += OBSERVATION_LAYOUT[i].1;
+        i += 1;
+    }
+    start
+}
+
+impl Environment {
+    /// The environment as a fixed-size array of numbers, laid out as described in [`crate::game::OBSERVATION_LAYOUT`].
+    pub fn encode(&self) -> [f32; OBSERVATION_SIZE] {
+        let mut observation = [0.0; OBSERVATION_SIZE];
+        let n_seats = self.n_seats();
+        let total_chips = self.total_chips().max(1) as f32;
+        // Seats counted on from the player to act, sharing the last slot past the tenth
+        let from_player = |seat: usize| ((seat + n_seats - self.current_player.seat) % n_seats).min(MAX_SEATS - 1);
+        let street = self.table_cards.len().saturating_sub(2);
+
+        for card in self.current_player.hand {
+            observation[offset(0) + card.index()] = 1.0;
+        }
+        for card in &self.table_cards {
+            observation[offset(1) + card.index()] = 1.0;
+        }
+        observation[offset(2) + street] = 1.0;
+        observation[offset(3) + ((self.current_player.seat + n_seats - self.button) % n_seats).min(MAX_SEATS - 1)] = 1.0;
+
+        let players = [(self.current_player.seat, self.current_player.balance, true)].into_iter()
+            .chain(self.player_states.iter().map(|x| (x.seat, x.balance, true)))
+            .chain(self.folded_players.iter().map(|x| (x.seat, x.balance, false)));
+        for (seat, (remaining, committed), in_hand) in players {
+            let slot = &mut observation[offset(4) + from_player(seat) * SEAT_FEATURES..][..SEAT_FEATURES];
+            slot.copy_from_slice(&[
+                1.0,
+                if in_hand { 1.0 } else { 0.0 },
+                if in_hand && remaining == 0 { 1.0 } else { 0.0 },
+                remaining as f32 / total_chips,
+                committed as f32 / total_chips
+            ]);
+        }
+
+        let call_cost = self.legal_actions.iter()
+            .find_map(|x| match x {
+                LegalAction::Call(cost) => Some(*cost),
+                _ => None
+            })
+            .unwrap_or(0);
+        let chips = [self.pot, call_cost, self.street_bet, self.betting_limits.minimum_raise];
+        for (slot, amount) in observation[offset(5)..].iter_mut().zip(chips) {
+            *slot = amount as f32 / total_chips;
+        }
+        for (slot, legal) in observation[offset(6)..].iter_mut().zip(self.action_mask()) {
+            *slot = if legal { 1.0 } else { 0.0 };
+        }
+
+        let actions = self.street_totals();
+        for (i, (street, ActionHistory(player_id, action), _, street_total)) in actions.into_iter().rev().take(MAX_ACTIONS).enumerate() {
+            let slot = &mut observation[offset(7) + i * ACTION_FEATURES..][..ACTION_FEATURES];
+            let kind = match action {
+                Action::Fold => ActionKind::Fold,
+                Action::Check => ActionKind::Check,
+                Action::Call => ActionKind::Call,
+                Action::Bet(_) => ActionKind::Bet,
+                Action::Raise(_) | Action::RaiseTo(_) => ActionKind::Raise,
+                Action::AllIn => ActionKind::AllIn,
+                Action::Post(..) => unreachable!("Forced bets are left out of the actions")
+            };
+            slot[ActionKind::ALL.iter().position(|x| *x == kind).expect("Every kind is in the list")] = 1.0;
+            slot[ActionKind::ALL.len() + street] = 1.0;
+            if let Some(seat) = self.seat_of(player_id) {
+                slot[ActionKind::ALL.len() + 4 + from_player(seat)] = 1.0;
+            }
+            if !matches!(kind, ActionKind::Fold | ActionKind::Check) {
+                slot[ACTION_FEATURES - 1] = street_total as f32 / total_chips;
+            }
+        }
+
+        observation
+    }
+
+    /// Players dealt into the hand, whether or not they have folded.
+    pub fn n_seats(&self) -> usize {
+        1 + self.player_states.len() + self.folded_players.len()
+    }
+
+    /// Every chip in the hand, both those in the pot, antes included, and those players have left.
+    pub fn total_chips(&self) -> usize {
+        self.pot + self.current_player.balance.0 + self.player_states.iter().chain(&self.folded_players).map(|x| x.balance.0).sum::<usize>()
+    }
+
+    // Every action but the forced bets with its street, the street bet it faced and the street total the
+    // player has in once it is made, played back from the stacks everyone started the hand with
+    pub(crate) fn street_totals(&self) -> Vec<(usize, &ActionHistory, usize, usize)> {
+        let mut remaining: HashMap<&str, usize> = [(&self.current_player.player_id, self.current_player.balance)].into_iter()
+            .chain(self.player_states.iter().chain(&self.folded_players).map(|x| (&x.player_id, x.balance)))
+            .map(|(player_id, (remaining, committed))| (player_id.as_str(), remaining + committed))
+            .collect();
+        // Antes came out of the stacks without counting towards anyone's bet
+        for ActionHistory(player_id, action) in self.game_history.iter().flatten() {
+            if let Action::Post(forced_bet, amount) = action {
+                if !forced_bet.is_live() {
+                    *remaining.entry(player_id).or_default() += amount;
+                }
+            }
+        }
+
+        let mut totals = Vec::new();
+        for (street, history) in self.game_history.iter().enumerate() {
+            let mut street_bets: HashMap<&str, usize> = HashMap::new();
+            let mut street_bet = 0;
+            for action_history @ ActionHistory(player_id, action) in history {
+                let (player_remaining, player_bet) = (remaining.entry(player_id).or_default(), street_bets.entry(player_id).or_default());
+                let target = match *action {
+                    Action::Post(forced_bet, amount) if !forced_bet.is_live() => {
+                        *player_remaining = player_remaining.saturating_sub(amount);
+                        continue;
+                    }
+                    Action::Post(_, amount) => *player_bet + amount,
+                    Action::Fold | Action::Check => *player_bet,
+                    Action::Call => street_bet.max(*player_bet).min(*player_bet + *player_remaining),
+                    Action::Bet(total) | Action::RaiseTo(total) => total,
+                    Action::Raise(amount) => street_bet + amount,
+                    Action::AllIn => *player_bet + *player_remaining
+                };
+                *player_remaining = player_remaining.saturating_sub(target.saturating_sub(*player_bet));
+                *player_bet = target.max(*player_bet);
+                if !matches!(action, Action::Post(..)) {
+                    totals.push((street, action_history, street_bet, target));
+                }
+                street_bet = street_bet.max(target);
+            }
+        }
+
+        totals
+    }
+
+    fn seat_of(&self, player_id: &str) -> Option<usize> {
+        if self.current_player.player_id == player_id {
+            return Some(self.current_player.seat);
+        }
+
+        self.player_states.iter()
+            .chain(&self.folded_players)
+            .find(|x| x.player_id == player_id)
+            .map(|x| x.seat)
+    }
+}
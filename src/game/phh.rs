@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+use std::iter::Peekable;
+use std::str::Chars;
+use itertools::Itertools;
+use crate::game::{Action, ActionHistory, BettingStructure, ForcedBet, ForcedBets, HandHistoryError, Player, RecordedHand, TableConfig};
+use crate::rules::parse_cards;
+
+// The few kinds of value a PHH file holds
+enum Value {
+    Text(String),
+    Number(String),
+    Array(Vec<Value>),
+    Other
+}
+
+/// Read a hand in the Poker Hand History (PHH) format.
+///
+/// Players are listed from the small blind round to the button, with the blinds swapped heads up.
+pub fn parse_phh(text: &str) -> Result<RecordedHand, HandHistoryError> {
+    let fields = parse_fields(text)?;
+    let malformed = |key: &str| HandHistoryError::Malformed(key.to_string());
+
+    let betting_structure = match fields.get("variant") {
+        Some(Value::Text(variant)) if variant == "NT" => BettingStructure::NoLimit,
+        Some(Value::Text(variant)) if variant == "FT" => BettingStructure::FixedLimit { raise_cap: 4 },
+        Some(Value::Text(variant)) => return Err(HandHistoryError::Unsupported(format!("the \"{}\" variant", variant))),
+        _ => return Err(malformed("variant"))
+    };
+    let stacks = numbers(&fields, "starting_stacks")?.ok_or_else(|| malformed("starting_stacks"))?;
+    let n_players = stacks.len();
+    if n_players < 2 {
+        return Err(malformed("starting_stacks"));
+    }
+    let player_ids: Vec<String> = match fields.get("players") {
+        Some(Value::Array(players)) => players.iter()
+            .map(|x| match x {
+                Value::Text(player_id) => Ok(player_id.clone()),
+                _ => Err(malformed("players"))
+            })
+            .collect::<Result<_, _>>()?,
+        _ => (1..=n_players).map(|i| format!("p{}", i)).collect()
+    };
+    let antes = numbers(&fields, "antes")?.unwrap_or_else(|| vec![0; n_players]);
+    let blinds_or_straddles = numbers(&fields, "blinds_or_straddles")?.unwrap_or_else(|| vec![0; n_players]);
+    let final_stacks = numbers(&fields, "finishing_stacks")?;
+    if [player_ids.len(), antes.len(), blinds_or_straddles.len()].into_iter().any(|x| x != n_players)
+        || final_stacks.as_ref().is_some_and(|x| x.len() != n_players) {
+        return Err(malformed("the number of players"));
+    }
+
+    // Heads up the button is the small blind, so the blinds are swapped
+    let (small_blind_seat, big_blind_seat) = if n_players == 2 { (1, 0) } else { (0, 1) };
+    let (small_blind, big_blind) = (blinds_or_straddles[small_blind_seat], blinds_or_straddles[big_blind_seat]);
+    let straddle = n_players > 2 && blinds_or_straddles[2] > 0;
+    let big_blind_ante = match antes.iter().positions(|x| *x > 0).collect_vec().as_slice() {
+        [seat] if *seat == big_blind_seat => antes[*seat],
+        _ => 0
+    };
+    let ante = if big_blind_ante > 0 { 0 } else { antes.iter().copied().max().unwrap_or(0) };
+
+    let mut actions = Vec::new();
+    let post = |seat: usize, forced_bet: ForcedBet, amount: usize| ActionHistory(player_ids[seat].clone(), Action::Post(forced_bet, amount.min(stacks[seat])));
+    for (seat, amount) in antes.iter().enumerate().filter(|x| *x.1 > 0) {
+        actions.push(post(seat, if big_blind_ante > 0 { ForcedBet::BigBlindAnte } else { ForcedBet::Ante }, *amount));
+    }
+    for (seat, amount) in blinds_or_straddles.iter().enumerate().filter(|x| *x.1 > 0) {
+        let forced_bet = match seat {
+            _ if seat == small_blind_seat => ForcedBet::SmallBlind,
+            _ if seat == big_blind_seat => ForcedBet::BigBlind,
+            2 => ForcedBet::Straddle,
+            _ => return Err(HandHistoryError::Unsupported("more than one straddle".to_string()))
+        };
+        actions.push(post(seat, forced_bet, *amount));
+    }
+
+    let mut hands = vec![None; n_players];
+    let mut board = Vec::new();
+    let Some(Value::Array(recorded)) = fields.get("actions") else {
+        return Err(malformed("actions"));
+    };
+    for action in recorded {
+        let Value::Text(action) = action else {
+            return Err(malformed("actions"));
+        };
+        let parts: Vec<&str> = action.split('#').next().unwrap_or_default().split_whitespace().collect();
+        let seat_of = |player: &str| player.strip_prefix('p')
+            .and_then(|x| x.parse::<usize>().ok())
+            .filter(|x| (1..=n_players).contains(x))
+            .map(|x| x - 1)
+            .ok_or_else(|| malformed(action));
+
+        match parts.as_slice() {
+            ["d", "dh", player, cards] => {
+                if !cards.contains('?') {
+                    hands[seat_of(player)?] = Some(parse_cards(cards)?.try_into().map_err(|_| malformed(action))?);
+                }
+            }
+            ["d", "db", cards] => board.extend(parse_cards(cards)?),
+            [player, "f"] => actions.push(ActionHistory(player_ids[seat_of(player)?].clone(), Action::Fold)),
+            [player, "cc"] => actions.push(ActionHistory(player_ids[seat_of(player)?].clone(), Action::Call)),
+            [player, "cbr", amount] => {
+                let amount = amount.parse().map_err(|_| malformed(action))?;
+                actions.push(ActionHistory(player_ids[seat_of(player)?].clone(), Action::RaiseTo(amount)));
+            }
+            [player, "sm", cards] if !cards.contains('?') && *cards != "-" => {
+                hands[seat_of(player)?] = Some(parse_cards(cards)?.try_into().map_err(|_| malformed(action))?);
+            }
+            [_, "sm", ..] => (),
+            _ => return Err(HandHistoryError::Unsupported(format!("the action \"{}\"", action)))
+        }
+    }
+
+    let hand_id = match fields.get("hand") {
+        Some(Value::Text(x) | Value::Number(x)) => x.clone(),
+        _ => String::new()
+    };
+
+    Ok(RecordedHand {
+        hand_id,
+        seats: player_ids.iter()
+            .zip(&stacks)
+            .map(|(player_id, balance)| Player { player_id: player_id.clone(), balance: *balance })
+            .collect(),
+        button: n_players - 1,
+        config: TableConfig {
+            betting_structure,
+            forced_bets: ForcedBets {
+                blinds: Some((small_blind, big_blind)),
+                ante,
+                big_blind_ante,
+                straddle
+            },
+            ..TableConfig::default()
+        },
+        hands,
+        board,
+        actions,
+        total_pot: None,
+        final_stacks
+    })
+}
+
+// A list of whole chip amounts, if the field is there
+fn numbers(fields: &HashMap<String, Value>, key: &str) -> Result<Option<Vec<usize>>, HandHistoryError> {
+    let Some(value) = fields.get(key) else {
+        return Ok(None);
+    };
+    let Value::Array(values) = value else {
+        return Err(HandHistoryError::Malformed(key.to_string()));
+    };
+
+    values.iter()
+        .map(|x| match x {
+            Value::Number(number) => number.parse().map_err(|_| HandHistoryError::Unsupported(format!("{} of {} chips", key, number))),
+            _ => Err(HandHistoryError::Malformed(key.to_string()))
+        })
+        .collect::<Result<_, _>>()
+        .map(Some)
+}
+
+// The `key = value` pairs of a PHH file, which is a small part of TOML
+fn parse_fields(text: &str) -> Result<HashMap<String, Value>, HandHistoryError> {
+    let mut fields = HashMap::new();
+    let mut chars = text.chars().peekable();
+
+    loop {
+        skip_blank(&mut chars);
+        match chars.peek() {
+            None => break,
+            // Table headers only group fields the engine does not use
+            Some('[') => {
+                chars.by_ref().take_while(|x| *x != '\n').for_each(drop);
+            }
+            Some(_) => {
+                let key: String = chars.by_ref().take_while(|x| *x != '=').collect();
+                let key = key.trim().trim_matches(['"', '\'']).to_string();
+                if key.is_empty() || key.contains('\n') {
+                    return Err(HandHistoryError::Malformed(key));
+                }
+                let value = parse_value(&mut chars).ok_or_else(|| HandHistoryError::Malformed(key.clone()))?;
+                fields.insert(key, value);
+            }
+        }
+    }
+
+    Ok(fields)
+}
+
+fn parse_value(chars: &mut Peekable<Chars>) -> Option<Value> {
+    skip_blank(chars);
+    match *chars.peek()? {
+        '[' => {
+            chars.next();
+            let mut values = Vec::new();
+            loop {
+                skip_blank(chars);
+                match chars.peek()? {
+                    ']' => {
+                        chars.next();
+                        return Some(Value::Array(values));
+                    }
+                    ',' => {
+                        chars.next();
+                    }
+                    _ => values.push(parse_value(chars)?)
+                }
+            }
+        }
+        quote @ ('"' | '\'') => {
+            chars.next();
+            let mut text = String::new();
+            loop {
+                match chars.next()? {
+                    '\\' if quote == '"' => text.push(chars.next()?),
+                    x if x == quote => return Some(Value::Text(text)),
+                    x => text.push(x)
+                }
+            }
+        }
+        _ => {
+            let mut text = String::new();
+            while let Some(x) = chars.next_if(|x| !matches!(x, ',' | ']' | '\n' | '#')) {
+                text.push(x);
+            }
+            let text = text.trim().replace('_', "");
+            if text.starts_with(|x: char| x.is_ascii_digit() || x == '-') {
+                Some(Value::Number(text))
+            } else {
+                Some(Value::Other)
+            }
+        }
+    }
+}
+
+// Whitespace and comments between values
+fn skip_blank(chars: &mut Peekable<Chars>) {
+    loop {
+        match chars.peek() {
+            Some(x) if x.is_whitespace() => {
+                chars.next();
+            }
+            Some('#') => {
+                chars.by_ref().take_while(|x| *x != '\n').for_each(drop);
+            }
+            _ => break
+        }
+    }
+}
@@ -1,14 +1,15 @@
 use crate::rules::Card;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DealtPlayer {
     pub player_id: String,
+    pub seat: usize,
     pub balance: (usize, usize),
-    pub hand: [Card; 2],
-    pub expectation: usize
+    pub hand: [Card; 2]
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Player {
     pub player_id: String,
     pub balance: usize
@@ -29,9 +30,10 @@ impl From<DealtPlayer> for Player {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DealtPlayerVisible {
     pub player_id: String,
+    pub seat: usize,
     pub balance: (usize, usize)
 }
 
@@ -39,12 +41,14 @@ impl From<DealtPlayer> for DealtPlayerVisible {
     fn from(value: DealtPlayer) -> Self {
         let DealtPlayer {
             player_id,
+            seat,
             balance,
             ..
         } = value;
 
         DealtPlayerVisible {
             balance,
+            seat,
             player_id
         }
     }
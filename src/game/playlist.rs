@@ -1,7 +1,8 @@
 use std::collections::VecDeque;
-use std::iter::from_fn;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone)]
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Playlist<T>(pub VecDeque<T>, pub VecDeque<T>, pub Vec<T>);
 
 impl <T> Playlist<T> {
@@ -34,6 +35,15 @@ impl <T> Playlist<T> {
         self.1.append(&mut self.0);
     }
 
+    /// Restart with the item with the smallest key first, keeping the order around the table.
+    pub fn restart_by_key<K: Ord, F>(&mut self, mut key: F) where F: FnMut(&T) -> K {
+        self.restart();
+
+        if let Some(first) = self.1.iter().position_min_by_key(|x| key(x)) {
+            self.1.rotate_left(first);
+        }
+    }
+
     pub fn restart_without_last(&mut self) {
         let front_man = self.0.pop_back();
 
@@ -45,7 +55,7 @@ impl <T> Playlist<T> {
     }
 
     pub fn is_finished(&self) -> bool {
-        self.1.len() == 0
+        self.1.is_empty()
     }
 
     pub fn len(&self) -> usize {
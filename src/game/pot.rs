@@ -0,0 +1,112 @@
+use itertools::Itertools;
+use crate::game::player::DealtPlayer;
+use crate::rules::{calculate_best_hand, Card};
+use serde::{Deserialize, Serialize};
+
+/// One layer of the pot, either the main pot or a side pot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Pot {
+    pub amount: usize,
+    // Players who put in enough to contest this pot and did not fold
+    pub eligible: Vec<String>,
+    // Players who were awarded a share of this pot, and how much each of them won
+    pub winners: Vec<(String, usize)>
+}
+
+/// Split the chips committed by every player into a main pot and side pots, with any dead money
+/// going into the main pot.
+///
+/// Each layer is capped at the contribution of the shortest stack still contesting it, so players
+/// can only win from each opponent as much as they put in themselves.
+pub fn build_pots(active_players: &[DealtPlayer], folded_players: &[DealtPlayer], dead_money: usize) -> Vec<Pot> {
+    let levels: Vec<usize> = active_players.iter()
+        .chain(folded_players.iter())
+        .map(|x| x.balance.1)
+        .filter(|x| *x > 0)
+        .sorted()
+        .dedup()
+        .collect();
+
+    let mut pots: Vec<Pot> = Vec::with_capacity(levels.len());
+    let mut previous_level = 0;
+
+    for level in levels {
+        let amount: usize = active_players.iter()
+            .chain(folded_players.iter())
+            .map(|x| x.balance.1.min(level) - x.balance.1.min(previous_level))
+            .sum();
+        let mut eligible: Vec<String> = active_players.iter()
+            .filter(|x| x.balance.1 >= level)
+            .map(|x| x.player_id.clone())
+            .collect();
+        previous_level = level;
+
+        // Chips nobody still in the hand has matched go to whoever is left
+        if eligible.is_empty() && pots.is_empty() {
+            eligible = active_players.iter().map(|x| x.player_id.clone()).collect();
+        }
+
+        match pots.last_mut() {
+            // Layers contested by the same players belong together
+            Some(last) if last.eligible == eligible || eligible.is_empty() => last.amount += amount,
+            _ => pots.push(Pot {
+                amount,
+                eligible,
+                winners: Vec::with_capacity(0)
+            })
+        }
+    }
+
+    match pots.first_mut() {
+        Some(main_pot) => main_pot.amount += dead_money,
+        None if dead_money > 0 => pots.push(Pot {
+            amount: dead_money,
+            eligible: active_players.iter().map(|x| x.player_id.clone()).collect(),
+            winners: Vec::with_capacity(0)
+        }),
+        None => ()
+    }
+
+    pots
+}
+
+/// Award every pot to the best eligible hands, splitting evenly on ties.
+pub fn distribute_pots(table_cards: &[Card], mut active_players: Vec<DealtPlayer>, folded_players: &[DealtPlayer], dead_money: usize) -> (Vec<DealtPlayer>, Vec<Pot>) {
+    let mut pots = build_pots(&active_players, folded_players, dead_money);
+
+    // A lone player left standing takes everything without a showdown
+    let hands: Vec<_> = if active_players.len() > 1 {
+        active_players.iter().map(|x| Some(calculate_best_hand(x.hand, table_cards))).collect()
+    } else {
+        vec![None; active_players.len()]
+    };
+
+    for pot in pots.iter_mut() {
+        let winner_list: Vec<usize> = active_players.iter()
+            .enumerate()
+            .filter(|(_, x)| pot.eligible.contains(&x.player_id))
+            .map(|(i, _)| i)
+            .max_set_by_key(|i| hands[*i]);
+
+        let share = pot.amount / winner_list.len();
+        // Hand out any odd chips left over from the split one at a time
+        let odd_chips = pot.amount % winner_list.len();
+
+        pot.winners = winner_list.into_iter()
+            .enumerate()
+            .map(|(n, i)| {
+                let winnings = share + usize::from(n < odd_chips);
+                active_players[i].balance.0 += winnings;
+
+                (active_players[i].player_id.clone(), winnings)
+            })
+            .collect();
+    }
+
+    // Reset all the current bets for all the dealt players
+    for player in active_players.iter_mut() {
+        player.balance.1 = 0;
+    }
+
+    (active_players, pots)
+}
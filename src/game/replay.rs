@@ -0,0 +1,231 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use itertools::Itertools;
+use rand::Rng;
+use crate::game::{Action, ActionHistory, Environment, GameState, IllegalAction, LegalAction, Player, Showdown, TableConfig};
+use crate::rules::{Card, CardParseError};
+use serde::{Deserialize, Serialize};
+
+/// A hand read from a hand history, ready to be played again through the engine.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedHand {
+    pub hand_id: String,
+    // Players and the chips they started the hand with, in seat order
+    pub seats: Vec<Player>,
+    pub button: usize,
+    pub config: TableConfig,
+    // Hole cards of every seat, where the history shows them
+    pub hands: Vec<Option<[Card; 2]>>,
+    pub board: Vec<Card>,
+    // Forced bets and then every action in the order they were made
+    pub actions: Vec<ActionHistory>,
+    // Chips that went into the pot, uncalled bets included
+    pub total_pot: Option<usize>,
+    // Chips each seat finished the hand with
+    pub final_stacks: Option<Vec<usize>>
+}
+
+/// A recorded hand played through the engine, and everywhere the engine disagreed with the record.
+pub struct Replay<R: Rng + Sized> {
+    pub game: GameState<R>,
+    // What the player saw before each recorded action, and the action the engine was given
+    pub steps: Vec<(Environment, Action)>,
+    pub divergences: Vec<Divergence>
+}
+
+/// A difference between a recorded hand and the engine playing it again.
+#[derive(Debug, Clone)]
+pub enum Divergence {
+    // The engine posted different forced bets to the ones recorded
+    ForcedBets {
+        recorded: Vec<ActionHistory>,
+        replayed: Vec<ActionHistory>
+    },
+    // The recorded action was made by someone other than the player the engine had to act
+    OutOfTurn {
+        recorded: ActionHistory,
+        to_act: String
+    },
+    IllegalAction(IllegalAction),
+    // The recorded actions ran out before the hand was over
+    Unfinished,
+    // The hand was over with this many recorded actions left
+    ExtraActions(usize),
+    Pot {
+        recorded: usize,
+        replayed: usize
+    },
+    Stack {
+        player_id: String,
+        recorded: usize,
+        replayed: usize
+    }
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let list = |history: &[ActionHistory]| history.iter().map(|ActionHistory(id, action)| format!("{}: {}", id, action)).join(", ");
+        match self {
+            Divergence::ForcedBets { recorded, replayed } => write!(f, "Forced bets were posted as [{}] instead of the recorded [{}]", list(replayed), list(recorded)),
+            Divergence::OutOfTurn { recorded: ActionHistory(player_id, action), to_act } => write!(f, "\"{}\" played {} out of turn, \"{}\" was to act", player_id, action, to_act),
+            Divergence::IllegalAction(illegal_action) => write!(f, "{}", illegal_action),
+            Divergence::Unfinished => write!(f, "The recorded actions ran out before the hand was over"),
+            Divergence::ExtraActions(n) => write!(f, "The hand was over with {} recorded actions left", n),
+            Divergence::Pot { recorded, replayed } => write!(f, "The pot was {} instead of the recorded {}", replayed, recorded),
+            Divergence::Stack { player_id, recorded, replayed } => write!(f, "\"{}\" finished with {} instead of the recorded {}", player_id, replayed, recorded)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum HandHistoryError {
+    // A line or field that could not be read
+    Malformed(String),
+    // Something the engine does not play, like another game
+    Unsupported(String)
+}
+
+impl Display for HandHistoryError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HandHistoryError::Malformed(line) => write!(f, "Failed to read hand history. Malformed: \"{}\"", line),
+            HandHistoryError::Unsupported(reason) => write!(f, "Failed to read hand history. Unsupported: {}", reason)
+        }
+    }
+}
+
+impl Error for HandHistoryError {
+}
+
+impl From<CardParseError> for HandHistoryError {
+    fn from(value: CardParseError) -> Self {
+        HandHistoryError::Malformed(value.0)
+    }
+}
+
+impl RecordedHand {
+    /// Play the hand again with the recorded cards, dealing any cards the history does not show at random.
+    pub fn replay<R: Rng + Sized>(&self, rng: R) -> Result<Replay<R>, HandHistoryError> {
+        self.check_cards()?;
+        let minimum_bet = self.config.forced_bets.blinds.map_or(0, |(small_blind, _)| small_blind);
+        let mut game = GameState::new_with_hands(rng, self.seats.clone(), &self.hands, &self.board, minimum_bet, self.button, self.config.clone());
+
+        let mut divergences = Vec::new();
+        let mut steps = Vec::new();
+        let (recorded_posts, actions): (Vec<ActionHistory>, Vec<ActionHistory>) = self.actions.iter()
+            .cloned()
+            .partition(|x| matches!(x.1, Action::Post(..)));
+
+        if let GameState::BettingRound(betting_round) = &game {
+            // Histories list the forced bets in their own order
+            let key = |x: &ActionHistory| (x.0.clone(), x.1.to_string());
+            let replayed_posts = betting_round.get_environment().game_history[0].clone();
+            if recorded_posts.iter().sorted_by_key(|x| key(x)).ne(replayed_posts.iter().sorted_by_key(|x| key(x))) {
+                divergences.push(Divergence::ForcedBets { recorded: recorded_posts, replayed: replayed_posts });
+            }
+        }
+
+        let mut actions = actions.into_iter().peekable();
+        while let GameState::BettingRound(betting_round) = &game {
+            let environment = betting_round.get_environment();
+            let recorded_next = actions.peek().is_some_and(|x| x.0 == environment.current_player.player_id);
+            let (environment, action) = if recorded_next || needs_decision(&environment) {
+                let Some(ActionHistory(player_id, action)) = actions.next() else {
+                    divergences.push(Divergence::Unfinished);
+                    break;
+                };
+                if player_id != environment.current_player.player_id {
+                    divergences.push(Divergence::OutOfTurn {
+                        recorded: ActionHistory(player_id, action),
+                        to_act: environment.current_player.player_id
+                    });
+                    break;
+                }
+
+                let action = engine_action(&environment, action);
+                (Some(environment), action)
+            } else {
+                (None, Action::Check)
+            };
+
+            let action = match betting_round.validate(action) {
+                Ok(action) => action,
+                Err(illegal_action) => {
+                    divergences.push(Divergence::IllegalAction(illegal_action));
+                    break;
+                }
+            };
+            let GameState::BettingRound(betting_round) = game else {
+                unreachable!("The hand was checked to be in progress above")
+            };
+            steps.extend(environment.map(|x| (x, action)));
+            game = betting_round.update_state(action).expect("The action was checked to be legal above");
+        }
+
+        if let GameState::Finished(showdown) = &game {
+            let extra_actions = actions.count();
+            if extra_actions > 0 {
+                divergences.push(Divergence::ExtraActions(extra_actions));
+            }
+            divergences.extend(self.compare_results(showdown));
+        }
+
+        Ok(Replay { game, steps, divergences })
+    }
+
+    fn compare_results(&self, showdown: &Showdown) -> Vec<Divergence> {
+        let mut divergences = Vec::new();
+        if let Some(recorded) = self.total_pot.filter(|x| *x != showdown.bet.0) {
+            divergences.push(Divergence::Pot { recorded, replayed: showdown.bet.0 });
+        }
+
+        let final_stacks = self.final_stacks.iter().flatten();
+        for ((player, recorded), replayed) in self.seats.iter().zip(final_stacks).zip(showdown.final_stacks()) {
+            if replayed != *recorded {
+                divergences.push(Divergence::Stack { player_id: player.player_id.clone(), recorded: *recorded, replayed });
+            }
+        }
+
+        divergences
+    }
+
+    // Whether the recorded cards can all be dealt to the recorded seats
+    fn check_cards(&self) -> Result<(), HandHistoryError> {
+        if self.seats.len() < 2 || self.button >= self.seats.len() || self.hands.len() != self.seats.len() || self.board.len() > 5 {
+            return Err(HandHistoryError::Malformed(format!("{} hands and {} board cards for {} seats", self.hands.len(), self.board.len(), self.seats.len())));
+        }
+        let known = self.hands.iter().flatten().flatten().chain(self.board.iter());
+        if let Some(card) = known.duplicates().next() {
+            return Err(HandHistoryError::Malformed(format!("{} is dealt twice", card.to_standard_notation())));
+        }
+
+        Ok(())
+    }
+}
+
+// All in players, and players left with nobody to bet against, still act in the engine but histories may leave them out
+fn needs_decision(environment: &Environment) -> bool {
+    let nobody_to_bet_against = environment.player_states.iter().all(|x| x.balance.0 == 0)
+        && environment.legal_actions.contains(&LegalAction::Check);
+
+    environment.current_player.balance.0 > 0 && !nobody_to_bet_against
+}
+
+// Bets and raises as the engine takes them on this street, going all in when they use the whole stack
+fn engine_action(environment: &Environment, action: Action) -> Action {
+    let total = match action {
+        Action::Bet(total) | Action::RaiseTo(total) => total,
+        Action::Raise(amount) => environment.street_bet + amount,
+        action => return action
+    };
+    let (remaining_balance, player_bet) = environment.current_player.balance;
+    let street_committed = (player_bet + environment.street_bet).saturating_sub(environment.expected_bet);
+
+    if total >= street_committed + remaining_balance {
+        Action::AllIn
+    } else if environment.street_bet == 0 {
+        Action::Bet(total)
+    } else {
+        Action::RaiseTo(total)
+    }
+}
@@ -0,0 +1,176 @@
+use rand::{Rng, SeedableRng};
+use crate::rules::Deck;
+use crate::game::{Action, BettingRound, GameState, IllegalAction, Player, Showdown, TableConfig};
+use serde::{Deserialize, Serialize};
+
+/// How the minimum bet grows over the course of a session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum BlindSchedule {
+    // Minimum Bet
+    Fixed(usize),
+    // Grows by `growth` times the starting bet every `hands_per_level` hands
+    Escalating {
+        starting_bet: usize,
+        hands_per_level: usize,
+        growth: f64
+    },
+    // Minimum bet of each level, the last level lasting forever
+    Levels {
+        minimum_bets: Vec<usize>,
+        hands_per_level: usize
+    }
+}
+
+impl BlindSchedule {
+    /// The minimum bet for the `hand_number`th hand of the session, counting from 1.
+    pub fn minimum_bet(&self, hand_number: usize) -> usize {
+        match self {
+            BlindSchedule::Fixed(minimum_bet) => *minimum_bet,
+            BlindSchedule::Escalating { starting_bet, hands_per_level, growth } => {
+                let escalated = hand_number as f64 / *hands_per_level as f64 * growth * *starting_bet as f64;
+                (escalated as usize).max(*starting_bet)
+            }
+            BlindSchedule::Levels { minimum_bets, hands_per_level } => {
+                let level = (hand_number.saturating_sub(1) / hands_per_level).min(minimum_bets.len() - 1);
+                minimum_bets[level]
+            }
+        }
+    }
+}
+
+impl Default for BlindSchedule {
+    fn default() -> Self {
+        BlindSchedule::Escalating {
+            starting_bet: 2,
+            hands_per_level: 5,
+            growth: 1.5
+        }
+    }
+}
+
+/// A run of hands between the same players, carrying stacks over until one player has all the chips.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Session<R: Rng + Sized> {
+    rng: R,
+    // Players still holding chips, in seat order
+    seats: Vec<Player>,
+    button: usize,
+    blind_schedule: BlindSchedule,
+    config: TableConfig,
+    hands_played: usize,
+    // Busted players, in the order they went out
+    eliminated: Vec<String>,
+    game: Option<GameState<R>>
+}
+
+impl <R: Rng + SeedableRng> Session<R> {
+    pub fn new(rng: R, players: Vec<Player>, blind_schedule: BlindSchedule, config: TableConfig) -> Self {
+        let (seats, eliminated): (Vec<Player>, Vec<Player>) = players.into_iter().partition(|x| x.balance > 0);
+
+        Session {
+            rng,
+            seats,
+            button: 0,
+            blind_schedule,
+            config,
+            hands_played: 0,
+            eliminated: eliminated.into_iter().map(|x| x.player_id).collect(),
+            game: None
+        }
+    }
+
+    /// Deal the next hand, unless one is already being played or the session is over.
+    pub fn start_hand(&mut self) -> bool {
+        if self.is_finished() || self.hand_in_progress() {
+            return false;
+        }
+
+        let minimum_bet = self.minimum_bet();
+        let rng = R::from_rng(&mut self.rng).expect("Seeding a hand from the session should not fail");
+        self.hands_played += 1;
+        let deck = Deck::new_with_rng(rng);
+        self.game = Some(GameState::new_with_deck(deck, self.seats.clone(), minimum_bet, self.button, self.config.clone()));
+
+        true
+    }
+
+    /// Play `action` for the current player, returning the showdown if it finished the hand.
+    pub fn advance(&mut self, action: Action) -> Result<Option<&Showdown>, IllegalAction> {
+        let Some(GameState::BettingRound(betting_round)) = &self.game else {
+            return Ok(None);
+        };
+        let action = betting_round.validate(action)?;
+        let Some(GameState::BettingRound(betting_round)) = self.game.take() else {
+            unreachable!("The hand was checked to be in progress above")
+        };
+
+        // Checked above, so applied without validating it again
+        match betting_round.apply(action) {
+            GameState::Finished(showdown) => {
+                let (seats, button) = showdown.next_hand();
+                self.eliminated.extend(
+                    self.seats.iter()
+                        .filter(|x| !seats.iter().any(|y| y.player_id == x.player_id))
+                        .map(|x| x.player_id.clone())
+                );
+                self.seats = seats;
+                self.button = button;
+                self.game = Some(GameState::Finished(showdown));
+
+                Ok(self.last_showdown())
+            }
+            game => {
+                self.game = Some(game);
+                Ok(None)
+            }
+        }
+    }
+}
+
+impl <R: Rng + Sized> Session<R> {
+    pub fn is_finished(&self) -> bool {
+        self.seats.len() < 2
+    }
+
+    pub fn hand_in_progress(&self) -> bool {
+        matches!(self.game, Some(GameState::BettingRound(_)))
+    }
+
+    pub fn betting_round(&self) -> Option<&BettingRound<R>> {
+        match &self.game {
+            Some(GameState::BettingRound(betting_round)) => Some(betting_round),
+            _ => None
+        }
+    }
+
+    /// The showdown of the hand played last, until the next hand starts.
+    pub fn last_showdown(&self) -> Option<&Showdown> {
+        match &self.game {
+            Some(GameState::Finished(showdown)) => Some(showdown),
+            _ => None
+        }
+    }
+
+    pub fn seats(&self) -> &[Player] {
+        &self.seats
+    }
+
+    pub fn eliminated(&self) -> &[String] {
+        &self.eliminated
+    }
+
+    pub fn button(&self) -> usize {
+        self.button
+    }
+
+    pub fn hands_played(&self) -> usize {
+        self.hands_played
+    }
+
+    /// The minimum bet of the current hand, or of the next one between hands.
+    pub fn minimum_bet(&self) -> usize {
+        let hand_number = if self.hand_in_progress() { self.hands_played } else { self.hands_played + 1 };
+
+        self.blind_schedule.minimum_bet(hand_number)
+    }
+}
@@ -0,0 +1,81 @@
+use std::fmt::{Display, Formatter};
+use serde::{Deserialize, Serialize};
+
+/// How much a player may bet or raise at a time.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum BettingStructure {
+    // Raises at least as big as the last one, up to the whole stack
+    #[default]
+    NoLimit,
+    // Raises at least as big as the last one, up to the size of the pot after calling
+    PotLimit,
+    // Raises of exactly the small bet before the turn and the big bet from then on, with at most
+    // `raise_cap` bets and raises each street (the big blind counting as the first)
+    FixedLimit {
+        raise_cap: usize
+    }
+}
+
+impl Display for BettingStructure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BettingStructure::NoLimit => write!(f, "no-limit"),
+            BettingStructure::PotLimit => write!(f, "pot-limit"),
+            BettingStructure::FixedLimit { .. } => write!(f, "fixed-limit")
+        }
+    }
+}
+
+/// The betting so far on the current street.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreetBetting {
+    // The expected bet when the street opened
+    pub opening_bet: usize,
+    // Size of the last full raise, which the next raise has to at least match
+    pub last_raise: usize,
+    // Full bets and raises made, counting the big blind before the flop
+    pub raises: usize,
+    // Seats that have acted since the last full bet or raise, who may only call an all in short of one
+    #[serde(default)]
+    pub acted: Vec<usize>
+}
+
+impl StreetBetting {
+    // The big blind is the smallest bet and raise on every street
+    pub fn pre_flop(big_blind: usize) -> Self {
+        StreetBetting {
+            opening_bet: 0,
+            last_raise: big_blind,
+            raises: 1,
+            acted: Vec::new()
+        }
+    }
+
+    pub fn open(expected_bet: usize, big_blind: usize) -> Self {
+        StreetBetting {
+            opening_bet: expected_bet,
+            last_raise: big_blind,
+            raises: 0,
+            acted: Vec::new()
+        }
+    }
+}
+
+/// The raises the betting structure allows the current player, before their stack is considered.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct BettingLimits {
+    pub structure: BettingStructure,
+    // Smallest amount the current bet can be raised by
+    pub minimum_raise: usize,
+    // Largest amount the current bet can be raised by, if the structure caps it
+    pub maximum_raise: Option<usize>,
+    // Bets and raises left on this street, if the structure caps them
+    pub raises_left: Option<usize>
+}
+
+impl BettingLimits {
+    /// Whether any raise is allowed at all.
+    pub fn can_raise(&self) -> bool {
+        self.raises_left != Some(0)
+    }
+}
@@ -1,17 +1,105 @@
-mod game;
-mod rules;
+// PyO3 0.19's macros emit impl blocks inside their generated functions
+#![allow(non_local_definitions)]
+
+pub mod game;
+pub mod rules;
 
 #[cfg(test)]
 mod tests;
 
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use itertools::Itertools;
+use numpy::{IntoPyArray, PyArray1, PyArray2};
+use numpy::ndarray::Array2;
+use pyo3::create_exception;
 use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
+use pyo3::pyclass::CompareOp;
 use rand::prelude::StdRng;
-use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+use rand::{Rng, SeedableRng};
+use rayon::prelude::*;
 use game::Player;
-use crate::game::{ActionHistory, DealtPlayer, DealtPlayerVisible, Environment, GameState};
+use crate::rules::{calculate_best_cards, calculate_best_hand, Card, Deck, Range};
+use crate::rules::evaluator::HandRank;
+use crate::rules::equity::{calculate_equity, calculate_range_equity, Equity, EquityError, Opponents};
+use crate::game::{Action, ActionHistory, ActionKind, BettingStructure, BlindSchedule, DealtPlayer, DealtPlayerVisible, Environment, ForcedBets, GameState, HandHistoryError, IllegalAction, IllegalActionPolicy, LegalAction, Pot, Session, TableConfig, OBSERVATION_LAYOUT, OBSERVATION_SIZE, OBSERVATION_VERSION};
 
-#[pyclass]
+create_exception!(poker_environment, IllegalActionError, PyValueError);
+
+impl From<IllegalAction> for PyErr {
+    fn from(value: IllegalAction) -> Self {
+        IllegalActionError::new_err(value.to_string())
+    }
+}
+
+impl From<HandHistoryError> for PyErr {
+    fn from(value: HandHistoryError) -> Self {
+        PyErr::new::<PyValueError, _>(value.to_string())
+    }
+}
+
+impl From<EquityError> for PyErr {
+    fn from(value: EquityError) -> Self {
+        PyErr::new::<PyValueError, _>(value.to_string())
+    }
+}
+
+fn parse_illegal_action_policy(policy: &str) -> PyResult<IllegalActionPolicy> {
+    match policy.to_lowercase().as_str() {
+        "reject" => Ok(IllegalActionPolicy::Reject),
+        "fold" => Ok(IllegalActionPolicy::Fold),
+        "call" => Ok(IllegalActionPolicy::Call),
+        _ => Err(PyErr::new::<PyValueError, _>("Illegal action policy must be one of \"reject\", \"fold\" or \"call\""))
+    }
+}
+
+// Cards given as `PyCard`s or written as the engine shows them or in standard notation, each dealt at most once
+fn parse_card_list(cards: &[PyCardArg]) -> PyResult<Vec<Card>> {
+    let parsed: Vec<Card> = cards.iter()
+        .map(|card| match card {
+            PyCardArg::Card(PyCard(card)) => Ok(*card),
+            PyCardArg::Text(text) => text.parse::<Card>().map_err(|e| PyErr::new::<PyValueError, _>(e.to_string()))
+        })
+        .collect::<PyResult<_>>()?;
+
+    if !parsed.iter().all_unique() {
+        return Err(PyErr::new::<PyValueError, _>("The same card cannot be dealt twice"));
+    }
+    Ok(parsed)
+}
+
+// Two hole cards for each hand and three to five table cards, none of them the same
+fn parse_hands_and_board(hands: &[&[PyCardArg]], board: &[PyCardArg]) -> PyResult<(Vec<[Card; 2]>, Vec<Card>)> {
+    let hands = hands.iter()
+        .map(|hand| parse_card_list(hand)?.try_into().map_err(|_| PyErr::new::<PyValueError, _>("A hand must be two cards")))
+        .collect::<PyResult<Vec<[Card; 2]>>>()?;
+    let board = parse_card_list(board)?;
+    if !(3..=5).contains(&board.len()) {
+        return Err(PyErr::new::<PyValueError, _>("The board must be three to five cards"));
+    }
+    if !hands.iter().flatten().chain(&board).all_unique() {
+        return Err(PyErr::new::<PyValueError, _>("The same card cannot be dealt twice"));
+    }
+
+    Ok((hands, board))
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|x| x.as_secs()).unwrap_or(0)
+}
+
+fn parse_betting_structure(structure: &str, raise_cap: usize) -> PyResult<BettingStructure> {
+    match structure.to_lowercase().as_str() {
+        "no-limit" => Ok(BettingStructure::NoLimit),
+        "pot-limit" => Ok(BettingStructure::PotLimit),
+        "fixed-limit" => Ok(BettingStructure::FixedLimit { raise_cap }),
+        _ => Err(PyErr::new::<PyValueError, _>("Betting structure must be one of \"no-limit\", \"pot-limit\" or \"fixed-limit\""))
+    }
+}
+
+#[pyclass(module = "poker_environment.poker_environment")]
 #[derive(Clone)]
 struct PyPokerPlayerInfo {
     #[pyo3(get)]
@@ -29,6 +117,10 @@ impl PyPokerPlayerInfo {
             balance
         }
     }
+
+    fn __getnewargs__(&self) -> (String, usize) {
+        (self.player_id.clone(), self.balance)
+    }
 }
 
 impl From<PyPokerPlayerInfo> for Player {
@@ -45,9 +137,215 @@ impl From<Player> for PyPokerPlayerInfo {
     }
 }
 
-#[pyclass]
+/// A playing card, ordered by value and then suit.
+#[pyclass(module = "poker_environment.poker_environment")]
+#[derive(Clone, Copy)]
+struct PyCard(Card);
+
+#[pymethods]
+impl PyCard {
+    #[new]
+    fn py_new(card: &str) -> PyResult<Self> {
+        card.parse()
+            .map(PyCard)
+            .map_err(|e: rules::CardParseError| PyErr::new::<PyValueError, _>(e.to_string()))
+    }
+
+    /// The card from 0 for the two of hearts to 51 for the ace of spades.
+    #[staticmethod]
+    fn from_index(index: usize) -> PyResult<Self> {
+        Card::from_index(index)
+            .map(PyCard)
+            .ok_or_else(|| PyErr::new::<PyValueError, _>("A card index must be from 0 to 51"))
+    }
+
+    // From 2 to 14 for an ace
+    #[getter]
+    fn rank(&self) -> u8 {
+        self.0.1 as u8
+    }
+
+    // One of "h", "d", "c" or "s"
+    #[getter]
+    fn suit(&self) -> String {
+        self.0.0.to_string().to_lowercase()
+    }
+
+    #[getter]
+    fn index(&self) -> usize {
+        self.0.index()
+    }
+
+    // Standard notation, like "Ah" or "Td"
+    #[getter]
+    fn notation(&self) -> String {
+        self.0.to_standard_notation()
+    }
+
+    fn __richcmp__(&self, other: &Self, op: CompareOp) -> bool {
+        op.matches(self.0.cmp(&other.0))
+    }
+
+    fn __hash__(&self) -> u64 {
+        self.0.index() as u64
+    }
+
+    /// The card as the engine writes it, like "HA", the same as the string forms of hands and table cards.
+    fn __str__(&self) -> String {
+        self.0.to_string()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("PyCard('{}')", self.0.to_standard_notation())
+    }
+
+    fn __getnewargs__(&self) -> (String,) {
+        (self.0.to_standard_notation(),)
+    }
+}
+
+// A card given from Python either as a `PyCard` or as text
+#[derive(Clone, FromPyObject)]
+enum PyCardArg {
+    Card(PyCard),
+    Text(String)
+}
+
+/// The best five card hand a player can make, ordered by strength.
+#[pyclass(module = "poker_environment.poker_environment")]
+#[derive(Clone)]
+struct PyHand {
+    // Like "Full House"
+    #[pyo3(get)]
+    category: String,
+    // From 1 for seven high to 7462 for a royal flush, equal exactly when hands tie
+    #[pyo3(get)]
+    rank: u16,
+    // The five cards making the hand, ordered by significance
+    #[pyo3(get)]
+    cards: Vec<PyCard>
+}
+
+#[pymethods]
+impl PyHand {
+    fn __richcmp__(&self, other: &Self, op: CompareOp) -> bool {
+        op.matches(self.rank.cmp(&other.rank))
+    }
+
+    fn __hash__(&self) -> u64 {
+        self.rank as u64
+    }
+
+    fn __repr__(&self) -> String {
+        let cards = self.cards.iter().map(|x| x.0.to_standard_notation()).join(" ");
+        format!("PyHand('{}', rank={}, cards='{}')", self.category, self.rank, cards)
+    }
+}
+
+/// How often a hand wins, ties and loses against its opponents.
+#[pyclass(module = "poker_environment.poker_environment")]
+#[derive(Clone)]
+struct PyEquity {
+    #[pyo3(get)]
+    win: f64,
+    #[pyo3(get)]
+    tie: f64,
+    #[pyo3(get)]
+    lose: f64,
+    // Share of the pot won on average, with ties split
+    #[pyo3(get)]
+    equity: f64,
+    #[pyo3(get)]
+    samples: usize,
+    // Whether every deal was enumerated rather than sampled
+    #[pyo3(get)]
+    exact: bool
+}
+
+#[pymethods]
+impl PyEquity {
+    fn __repr__(&self) -> String {
+        format!("PyEquity(win={:.4}, tie={:.4}, lose={:.4}, equity={:.4}, samples={}, exact={})",
+                self.win, self.tie, self.lose, self.equity, self.samples, if self.exact { "True" } else { "False" })
+    }
+}
+
+impl From<Equity> for PyEquity {
+    fn from(value: Equity) -> Self {
+        let Equity { win, tie, lose, equity, samples, exact } = value;
+        PyEquity { win, tie, lose, equity, samples, exact }
+    }
+}
+
+/// A weighted set of hole card combinations, read from notation like "QQ+, AKs, KQo:0.5, 22-55".
+#[pyclass(module = "poker_environment.poker_environment")]
+#[derive(Clone)]
+struct PyRange(Range);
+
+#[pymethods]
+impl PyRange {
+    #[new]
+    fn py_new(notation: &str) -> PyResult<Self> {
+        notation.parse()
+            .map(PyRange)
+            .map_err(|e: rules::RangeParseError| PyErr::new::<PyValueError, _>(e.to_string()))
+    }
+
+    // Each combination, highest card first, and its weight
+    #[getter]
+    fn combos(&self) -> Vec<((PyCard, PyCard), f64)> {
+        self.0.combos().iter().map(|([first, second], weight)| ((PyCard(*first), PyCard(*second)), *weight)).collect()
+    }
+
+    /// The range without the combinations holding any of `cards`.
+    fn without(&self, cards: Vec<PyCardArg>) -> PyResult<Self> {
+        Ok(PyRange(self.0.without(&parse_card_list(&cards)?)))
+    }
+
+    fn __len__(&self) -> usize {
+        self.0.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("PyRange({} combos)", self.0.len())
+    }
+}
+
+// A range given from Python as a `PyRange`, in range notation or as a list of hands
+#[derive(FromPyObject)]
+enum PyRangeArg {
+    Range(PyRange),
+    Notation(String),
+    Hands(Vec<Vec<PyCardArg>>)
+}
+
+impl PyRangeArg {
+    fn into_range(self) -> PyResult<Range> {
+        match self {
+            PyRangeArg::Range(PyRange(range)) => Ok(range),
+            PyRangeArg::Notation(notation) => PyRange::py_new(&notation).map(|x| x.0),
+            PyRangeArg::Hands(hands) => Ok(Range::from_combos(hands.iter()
+                .map(|x| parse_card_list(x)?
+                    .try_into()
+                    .map(|x| (x, 1.0))
+                    .map_err(|_| PyErr::new::<PyValueError, _>("A hand must be two cards")))
+                .collect::<PyResult<Vec<_>>>()?))
+        }
+    }
+}
+
+// `opponents` random hands, unless there are ranges for each opponent
+fn parse_opponents(opponents: usize, ranges: Option<Vec<PyRangeArg>>) -> PyResult<Opponents> {
+    match ranges {
+        None => Ok(Opponents::Random(opponents)),
+        Some(ranges) => Ok(Opponents::Ranges(ranges.into_iter().map(PyRangeArg::into_range).collect::<PyResult<_>>()?))
+    }
+}
+
+#[pyclass(module = "poker_environment.poker_environment")]
 struct PyPokerGame {
-    game: GameState<StdRng>
+    // The same generator as `StdRng`, but one that can be saved along with the game
+    game: GameState<ChaCha12Rng>
 }
 
 #[pyclass]
@@ -56,20 +354,26 @@ struct PyPokerDealtPlayer {
     #[pyo3(get)]
     player_id: String,
     #[pyo3(get)]
+    seat: usize,
+    #[pyo3(get)]
     remaining_balance: usize,
     #[pyo3(get)]
     committed_balance: usize,
     #[pyo3(get)]
-    hand: Vec<String>
+    hand: Vec<String>,
+    #[pyo3(get)]
+    cards: Vec<PyCard>
 }
 
 impl From<DealtPlayer> for PyPokerDealtPlayer {
     fn from(value: DealtPlayer) -> Self {
         PyPokerDealtPlayer {
             player_id: value.player_id,
+            seat: value.seat,
             remaining_balance: value.balance.0,
             committed_balance: value.balance.1,
-            hand: value.hand.into_iter().map(|x| format!("{}", x)).collect()
+            hand: value.hand.iter().map(|x| format!("{}", x)).collect(),
+            cards: value.hand.into_iter().map(PyCard).collect()
         }
     }
 }
@@ -80,6 +384,8 @@ struct PyPokerDealtPlayerVisible {
     #[pyo3(get)]
     player_id: String,
     #[pyo3(get)]
+    seat: usize,
+    #[pyo3(get)]
     remaining_balance: usize,
     #[pyo3(get)]
     committed_balance: usize
@@ -89,6 +395,7 @@ impl From<DealtPlayerVisible> for PyPokerDealtPlayerVisible {
     fn from(value: DealtPlayerVisible) -> Self {
         PyPokerDealtPlayerVisible {
             player_id: value.player_id,
+            seat: value.seat,
             remaining_balance: value.balance.0,
             committed_balance: value.balance.1
         }
@@ -115,54 +422,197 @@ impl From<ActionHistory> for PyPokerActionHistory {
     }
 }
 
+#[pyclass]
+#[derive(Clone)]
+struct PyPokerPot {
+    #[pyo3(get)]
+    amount: usize,
+    #[pyo3(get)]
+    eligible_players: Vec<String>,
+    #[pyo3(get)]
+    winners: Vec<(String, usize)>
+}
+
+impl From<Pot> for PyPokerPot {
+    fn from(value: Pot) -> Self {
+        PyPokerPot {
+            amount: value.amount,
+            eligible_players: value.eligible,
+            winners: value.winners
+        }
+    }
+}
+
+#[pyclass]
+#[derive(Clone)]
+struct PyPokerLegalAction {
+    #[pyo3(get)]
+    action: String,
+    // Chips the action costs (calls and all ins) or the range of street totals it can be made for (bets and raises)
+    #[pyo3(get)]
+    minimum: usize,
+    #[pyo3(get)]
+    maximum: usize
+}
+
+impl From<LegalAction> for PyPokerLegalAction {
+    fn from(value: LegalAction) -> Self {
+        let (minimum, maximum) = match value {
+            LegalAction::Fold | LegalAction::Check => (0, 0),
+            LegalAction::Call(cost) | LegalAction::AllIn(cost) => (cost, cost),
+            LegalAction::Bet { minimum, maximum } | LegalAction::Raise { minimum, maximum } => (minimum, maximum)
+        };
+
+        PyPokerLegalAction {
+            action: value.kind().to_string(),
+            minimum,
+            maximum
+        }
+    }
+}
+
 #[pyclass]
 #[derive(Clone)]
 struct PyPokerEnvironment {
     #[pyo3(get)]
     table_cards: Vec<String>,
     #[pyo3(get)]
+    board: Vec<PyCard>,
+    #[pyo3(get)]
     current_player: PyPokerDealtPlayer,
     #[pyo3(get)]
     player_states: Vec<PyPokerDealtPlayerVisible>,
     #[pyo3(get)]
+    folded_players: Vec<PyPokerDealtPlayerVisible>,
+    #[pyo3(get)]
     game_history: PyPokerGameHistory,
     #[pyo3(get)]
     pot: usize,
     #[pyo3(get)]
     minimum_bet: usize,
     #[pyo3(get)]
-    expected_bet: usize
+    expected_bet: usize,
+    #[pyo3(get)]
+    street_bet: usize,
+    #[pyo3(get)]
+    betting_structure: String,
+    // Raise sizes the betting structure allows, and how many raises are left if it caps them
+    #[pyo3(get)]
+    minimum_raise: usize,
+    #[pyo3(get)]
+    maximum_raise: Option<usize>,
+    #[pyo3(get)]
+    raises_left: Option<usize>,
+    #[pyo3(get)]
+    button: usize,
+    #[pyo3(get)]
+    legal_actions: Vec<PyPokerLegalAction>,
+    #[pyo3(get)]
+    action_mask: Vec<bool>,
+    environment: Environment
 }
 
 impl From<Environment> for PyPokerEnvironment {
     fn from(value: Environment) -> Self {
+        let action_mask = value.action_mask().to_vec();
+        let environment = value.clone();
+
         Self {
-            table_cards: value.table_cards.into_iter().map(|x| format!("{}", x)).collect(),
+            table_cards: value.table_cards.iter().map(|x| format!("{}", x)).collect(),
+            board: value.table_cards.into_iter().map(PyCard).collect(),
             current_player: value.current_player.into(),
             player_states: value.player_states.into_iter().map(|x| x.into()).collect(),
+            folded_players: value.folded_players.into_iter().map(|x| x.into()).collect(),
             game_history: value.game_history.into_iter().map(|x| x.into_iter().map(|x| x.into()).collect()).collect(),
             pot: value.pot,
             expected_bet: value.expected_bet,
-            minimum_bet: value.minimum_bet
+            minimum_bet: value.minimum_bet,
+            street_bet: value.street_bet,
+            betting_structure: value.betting_limits.structure.to_string(),
+            minimum_raise: value.betting_limits.minimum_raise,
+            maximum_raise: value.betting_limits.maximum_raise,
+            raises_left: value.betting_limits.raises_left,
+            button: value.button,
+            action_mask,
+            legal_actions: value.legal_actions.into_iter().map(|x| x.into()).collect(),
+            environment
         }
     }
 }
 
+#[pymethods]
+impl PyPokerEnvironment {
+    /// The environment as a float32 array of `OBSERVATION_SIZE` numbers, laid out as in `OBSERVATION_LAYOUT`.
+    fn encode<'py>(&self, py: Python<'py>) -> &'py PyArray1<f32> {
+        self.environment.encode().to_vec().into_pyarray(py)
+    }
+
+    /// What the player to act knows, as a key for a solver's tables.
+    #[pyo3(signature = (suit_isomorphism=false))]
+    fn info_set_key(&self, suit_isomorphism: bool) -> String {
+        self.environment.info_set_key(suit_isomorphism)
+    }
+
+    #[pyo3(signature = (suit_isomorphism=false))]
+    fn info_set_hash(&self, suit_isomorphism: bool) -> u64 {
+        self.environment.info_set_hash(suit_isomorphism)
+    }
+}
+
 #[pymethods]
 impl PyPokerGame {
     #[new]
-    fn py_new(players: Vec<PyPokerPlayerInfo>, minimum_bet: usize, seed: u64) -> Self {
-        Self {
-            game: GameState::new_with_players(StdRng::seed_from_u64(seed), players.into_iter().map(|x| x.into()).collect(), minimum_bet)
+    #[pyo3(signature = (players, minimum_bet, seed, button=0, burn_cards=false, illegal_action_policy="reject", betting_structure="no-limit", raise_cap=4, blinds=None, ante=0, big_blind_ante=0, straddle=false, deck=None, hands=None, board=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn py_new(players: Vec<PyPokerPlayerInfo>, minimum_bet: usize, seed: u64, button: usize, burn_cards: bool, illegal_action_policy: &str, betting_structure: &str, raise_cap: usize, blinds: Option<(usize, usize)>, ante: usize, big_blind_ante: usize, straddle: bool, deck: Option<Vec<PyCardArg>>, hands: Option<Vec<Option<Vec<PyCardArg>>>>, board: Option<Vec<PyCardArg>>) -> PyResult<Self> {
+        if button >= players.len() {
+            return Err(PyErr::new::<PyValueError, _>("The button must be on one of the seats"));
         }
+
+        let config = TableConfig {
+            illegal_action_policy: parse_illegal_action_policy(illegal_action_policy)?,
+            betting_structure: parse_betting_structure(betting_structure, raise_cap)?,
+            forced_bets: ForcedBets { blinds, ante, big_blind_ante, straddle }
+        };
+        let rng = ChaCha12Rng::seed_from_u64(seed);
+        let deck = match (deck, hands, board) {
+            (None, None, None) => Deck::new_with_rng(rng).with_burn_cards(burn_cards),
+            (Some(cards), None, None) => {
+                // The deck is dealt exactly in this order, so with burn cards the burns come from it too
+                let cards = parse_card_list(&cards)?;
+                Deck::new_stacked(rng, &cards).with_burn_cards(burn_cards)
+            }
+            (None, hands, board) => {
+                let mut dealt = hands.unwrap_or_default()
+                    .iter()
+                    .map(|hand| hand.as_ref()
+                        .map(|x| parse_card_list(x)?.try_into().map_err(|_| PyErr::new::<PyValueError, _>("Each hand must be two cards")))
+                        .transpose())
+                    .collect::<PyResult<Vec<Option<[Card; 2]>>>>()?;
+                let board = parse_card_list(&board.unwrap_or_default())?;
+                if dealt.len() > players.len() || board.len() > 5 {
+                    return Err(PyErr::new::<PyValueError, _>("There can only be a hand for each player and five cards on the board"));
+                }
+                if !dealt.iter().flatten().flatten().chain(&board).all_unique() {
+                    return Err(PyErr::new::<PyValueError, _>("The same card cannot be dealt twice"));
+                }
+                dealt.resize(players.len(), None);
+                Deck::new_with_rng(rng).with_burn_cards(burn_cards).with_hands(&dealt, &board)
+            }
+            _ => return Err(PyErr::new::<PyValueError, _>("Cannot stack the whole deck and deal set hands at once"))
+        };
+        Ok(Self {
+            game: GameState::new_with_deck(deck, players.into_iter().map(|x| x.into()).collect(), minimum_bet, button, config)
+        })
     }
 
+    /// Raises `IllegalActionError` listing the legal actions if the table rejects the action.
     fn advance(&mut self, action: String) -> PyResult<()> {
         let action_parsed = action.try_into()
             .map_err(|_| PyErr::new::<PyValueError, _>("Failed to parse action"))?;
 
         if let GameState::BettingRound(br) = &self.game {
-            self.game = br.clone().update_state(action_parsed)
+            self.game = br.clone().update_state(action_parsed)?
         }
 
         Ok(())
@@ -179,24 +629,542 @@ impl PyPokerGame {
         }
     }
 
+    /// What the player in `seat` knows, whether or not it is their turn.
+    #[pyo3(signature = (seat, suit_isomorphism=false))]
+    fn info_set_key(&self, seat: usize, suit_isomorphism: bool) -> PyResult<String> {
+        match &self.game {
+            GameState::BettingRound(br) => br.info_set_key(seat, suit_isomorphism)
+                .ok_or_else(|| PyErr::new::<PyValueError, _>("There is no player in that seat")),
+            GameState::Finished(_) => Err(PyErr::new::<PyValueError, _>("Cannot get information set of finished game!"))
+        }
+    }
+
     fn get_players(&self) -> PyResult<(Vec<PyPokerDealtPlayer>, Vec<PyPokerPlayerInfo>)> {
         match &self.game {
             GameState::BettingRound(br) => Ok((Vec::with_capacity(0), br.get_players().into_iter().map(|x| Player::from(x).into()).collect())),
             GameState::Finished(s) => Ok((s.players.0.iter().map(|x| PyPokerDealtPlayer::from(x.clone())).collect(), s.players.1.iter().map(|x| PyPokerPlayerInfo::from(x.clone())).collect()))
         }
     }
+
+    /// The chips each player won or lost over the finished hand, in seat order.
+    fn get_chip_deltas(&self) -> PyResult<Vec<(String, isize)>> {
+        match &self.game {
+            GameState::BettingRound(_) => Err(PyErr::new::<PyValueError, _>("Cannot get chip deltas of unfinished game!")),
+            GameState::Finished(s) => Ok(s.seats.iter().cloned().zip(s.chip_deltas()).collect())
+        }
+    }
+
+    fn next_hand(&self) -> PyResult<(Vec<PyPokerPlayerInfo>, usize)> {
+        match &self.game {
+            GameState::BettingRound(_) => Err(PyErr::new::<PyValueError, _>("Cannot start the next hand of unfinished game!")),
+            GameState::Finished(s) => {
+                let (players, button) = s.next_hand();
+                Ok((players.into_iter().map(|x| x.into()).collect(), button))
+            }
+        }
+    }
+
+    fn get_pots(&self) -> PyResult<Vec<PyPokerPot>> {
+        match &self.game {
+            GameState::BettingRound(_) => Err(PyErr::new::<PyValueError, _>("Cannot get pots of unfinished game!")),
+            GameState::Finished(s) => Ok(s.pots.iter().map(|x| x.clone().into()).collect())
+        }
+    }
+
+    /// The finished hand written out as a PokerStars hand history, seen by the player in seat `hero`.
+    #[pyo3(signature = (hand_id=1, table_name="Poker Environment", hero=0))]
+    fn hand_history(&self, hand_id: u64, table_name: &str, hero: usize) -> PyResult<String> {
+        match &self.game {
+            GameState::BettingRound(_) => Err(PyErr::new::<PyValueError, _>("Cannot write the hand history of unfinished game!")),
+            GameState::Finished(s) if hero >= s.seats.len() => Err(PyErr::new::<PyValueError, _>(format!("There is no seat {}!", hero))),
+            GameState::Finished(s) => Ok(s.to_pokerstars(hand_id, table_name, unix_time(), hero))
+        }
+    }
+
+    /// The whole game as JSON, including the state of its random number generator.
+    fn to_json(&self) -> PyResult<String> {
+        serde_json::to_string(&self.game)
+            .map_err(|e| PyErr::new::<PyValueError, _>(format!("Failed to save game: {}", e)))
+    }
+
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        let game = serde_json::from_str(json)
+            .map_err(|e| PyErr::new::<PyValueError, _>(format!("Failed to load game: {}", e)))?;
+
+        Ok(Self { game })
+    }
+
+    /// The game's pickled state, which is its JSON.
+    fn __getstate__(&self) -> PyResult<String> {
+        self.to_json()
+    }
+
+    /// Restore the game from the state given by `__getstate__`.
+    fn __setstate__(&mut self, state: &str) -> PyResult<()> {
+        *self = Self::from_json(state)?;
+
+        Ok(())
+    }
+
+    // Pickles go through `from_json`, so loading one never deals a game of its own
+    fn __reduce__(&self, py: Python) -> PyResult<(PyObject, (String,))> {
+        Ok((py.get_type::<PyPokerGame>().getattr("from_json")?.into(), (self.__getstate__()?,)))
+    }
+}
+
+#[pyclass]
+struct PyPokerSession {
+    session: Session<StdRng>
 }
 
+#[pymethods]
+impl PyPokerSession {
+    #[new]
+    #[pyo3(signature = (players, seed, starting_bet=2, hands_per_level=5, growth=1.5, blind_levels=None, illegal_action_policy="reject", betting_structure="no-limit", raise_cap=4, blinds=None, ante=0, big_blind_ante=0, straddle=false))]
+    #[allow(clippy::too_many_arguments)]
+    fn py_new(players: Vec<PyPokerPlayerInfo>, seed: u64, starting_bet: usize, hands_per_level: usize, growth: f64, blind_levels: Option<Vec<usize>>, illegal_action_policy: &str, betting_structure: &str, raise_cap: usize, blinds: Option<(usize, usize)>, ante: usize, big_blind_ante: usize, straddle: bool) -> PyResult<Self> {
+        if hands_per_level == 0 {
+            return Err(PyErr::new::<PyValueError, _>("Blind levels must last at least one hand"));
+        }
+
+        let blind_schedule = match blind_levels {
+            Some(minimum_bets) if minimum_bets.is_empty() => return Err(PyErr::new::<PyValueError, _>("Blind levels cannot be empty")),
+            Some(minimum_bets) => BlindSchedule::Levels { minimum_bets, hands_per_level },
+            None => BlindSchedule::Escalating { starting_bet, hands_per_level, growth }
+        };
+
+        let config = TableConfig {
+            illegal_action_policy: parse_illegal_action_policy(illegal_action_policy)?,
+            betting_structure: parse_betting_structure(betting_structure, raise_cap)?,
+            forced_bets: ForcedBets { blinds, ante, big_blind_ante, straddle }
+        };
+
+        Ok(Self {
+            session: Session::new(StdRng::seed_from_u64(seed), players.into_iter().map(|x| x.into()).collect(), blind_schedule, config)
+        })
+    }
+
+    fn is_finished(&self) -> PyResult<bool> {
+        Ok(self.session.is_finished())
+    }
+
+    fn hand_in_progress(&self) -> PyResult<bool> {
+        Ok(self.session.hand_in_progress())
+    }
+
+    fn start_hand(&mut self) -> PyResult<()> {
+        if self.session.start_hand() {
+            Ok(())
+        } else {
+            Err(PyErr::new::<PyValueError, _>("Cannot start a hand while one is in progress or the session is finished!"))
+        }
+    }
+
+    /// Returns the pots awarded if the action finished the hand.
+    /// Raises `IllegalActionError` listing the legal actions if the table rejects the action.
+    fn advance(&mut self, action: String) -> PyResult<Option<Vec<PyPokerPot>>> {
+        let action_parsed = action.try_into()
+            .map_err(|_| PyErr::new::<PyValueError, _>("Failed to parse action"))?;
+
+        if !self.session.hand_in_progress() {
+            return Err(PyErr::new::<PyValueError, _>("Cannot advance without a hand in progress!"));
+        }
+
+        Ok(self.session.advance(action_parsed)?.map(|s| s.pots.iter().map(|x| x.clone().into()).collect()))
+    }
+
+    fn get_environment(&self) -> PyResult<PyPokerEnvironment> {
+        match self.session.betting_round() {
+            Some(br) => Ok(br.get_environment().into()),
+            None => Err(PyErr::new::<PyValueError, _>("Cannot get environment without a hand in progress!"))
+        }
+    }
+
+    /// Players still holding chips in seat order, with their stacks as of the start of the current hand.
+    fn get_players(&self) -> PyResult<Vec<PyPokerPlayerInfo>> {
+        Ok(self.session.seats().iter().map(|x| x.clone().into()).collect())
+    }
+
+    fn get_eliminated(&self) -> PyResult<Vec<String>> {
+        Ok(self.session.eliminated().to_vec())
+    }
+
+    fn hands_played(&self) -> PyResult<usize> {
+        Ok(self.session.hands_played())
+    }
+
+    fn minimum_bet(&self) -> PyResult<usize> {
+        Ok(self.session.minimum_bet())
+    }
+
+    fn button(&self) -> PyResult<usize> {
+        Ok(self.session.button())
+    }
+
+    /// The chips each player won or lost over the hand played last, in seat order, until the next hand starts.
+    fn get_chip_deltas(&self) -> PyResult<Option<Vec<(String, isize)>>> {
+        Ok(self.session.last_showdown().map(|s| s.seats.iter().cloned().zip(s.chip_deltas()).collect()))
+    }
+
+    /// The hand played last as a PokerStars hand history seen by the player in seat `hero`, until the next hand starts.
+    #[pyo3(signature = (table_name="Poker Environment", hero=0))]
+    fn hand_history(&self, table_name: &str, hero: usize) -> PyResult<Option<String>> {
+        match self.session.last_showdown() {
+            Some(s) if hero >= s.seats.len() => Err(PyErr::new::<PyValueError, _>(format!("There is no seat {}!", hero))),
+            s => Ok(s.map(|s| s.to_pokerstars(self.session.hands_played() as u64, table_name, unix_time(), hero)))
+        }
+    }
+}
+
+// One table of a `PyPokerVecGame`, dealing every hand from fresh stacks and its own generator
+struct VecTable {
+    rng: StdRng,
+    game: Option<GameState<StdRng>>,
+    button: usize,
+    players: Vec<Player>,
+    minimum_bet: usize,
+    config: TableConfig
+}
+
+impl VecTable {
+    fn new(seed: u64, players: Vec<Player>, minimum_bet: usize, config: TableConfig) -> Self {
+        let mut table = VecTable { rng: StdRng::seed_from_u64(seed), game: None, button: 0, players, minimum_bet, config };
+        table.deal();
+        table
+    }
+
+    // A new hand, with the button one seat on from the last hand
+    fn deal(&mut self) {
+        let deck = Deck::new_with_rng(StdRng::from_rng(&mut self.rng).expect("Seeding a hand from the table should not fail"));
+        self.game = Some(GameState::new_with_deck(deck, self.players.clone(), self.minimum_bet, self.button, self.config.clone()));
+        self.button = (self.button + 1) % self.players.len();
+    }
+
+    // The action the current player makes for the kind at `action`, after the table's illegal action policy
+    fn validate(&self, action: usize, bet_fraction: Option<f64>) -> Result<Action, IllegalAction> {
+        let Some(GameState::BettingRound(betting_round)) = &self.game else {
+            unreachable!("Every table is dealt a new hand once the last one finishes")
+        };
+
+        betting_round.validate(betting_round.get_environment().action_of_kind(ActionKind::ALL[action], bet_fraction))
+    }
+
+    // Play an action already validated, returning the chip deltas and dealing again if the hand finished
+    fn step(&mut self, action: Action) -> Option<Vec<isize>> {
+        let Some(GameState::BettingRound(betting_round)) = self.game.take() else {
+            unreachable!("Every table is dealt a new hand once the last one finishes")
+        };
+
+        // Checked in `validate`, so applied without validating it again
+        match betting_round.apply(action) {
+            GameState::Finished(showdown) => {
+                self.deal();
+                Some(showdown.chip_deltas())
+            }
+            game => {
+                self.game = Some(game);
+                None
+            }
+        }
+    }
+
+    fn environment(&self) -> Option<Environment> {
+        match &self.game {
+            Some(GameState::BettingRound(betting_round)) => Some(betting_round.get_environment()),
+            _ => None
+        }
+    }
+}
+
+/// Many tables stepped together in Rust, each playing one hand per episode from fresh stacks.
+///
+/// Every step takes an action kind for the current player at each table, as an index into
+/// `ACTION_MASK_KINDS`. Finished hands are dealt again straight away.
+#[pyclass]
+struct PyPokerVecGame {
+    tables: Vec<VecTable>,
+    players: Vec<Player>,
+    minimum_bet: usize,
+    config: TableConfig,
+    bet_fraction: Option<f64>,
+    parallel: bool
+}
+
+impl PyPokerVecGame {
+    // Observations and action masks of the current player at every table
+    fn observe<'py>(&self, py: Python<'py>) -> (&'py PyArray2<f32>, &'py PyArray2<bool>) {
+        let environments: Vec<Environment> = self.tables.iter()
+            .map(|x| x.environment().expect("Every table is dealt a new hand once the last one finishes"))
+            .collect();
+        let observations = environments.iter().flat_map(|x| x.encode()).collect();
+        let masks = environments.iter().flat_map(|x| x.action_mask()).collect();
+
+        (
+            Array2::from_shape_vec((self.tables.len(), OBSERVATION_SIZE), observations).expect("Every observation is the same size").into_pyarray(py),
+            Array2::from_shape_vec((self.tables.len(), ActionKind::ALL.len()), masks).expect("Every mask is the same size").into_pyarray(py)
+        )
+    }
+
+    // Tables seeded one after another from `seed`, each dealt its first hand
+    fn seed_tables(&mut self, n_games: usize, seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        self.tables = (0..n_games)
+            .map(|_| VecTable::new(rng.gen(), self.players.clone(), self.minimum_bet, self.config.clone()))
+            .collect();
+    }
+}
+
+#[pymethods]
+impl PyPokerVecGame {
+    #[new]
+    #[pyo3(signature = (n_games, n_players=2, stack=200, blinds=(1, 2), seed=0, betting_structure="no-limit", raise_cap=4, bet_fraction=None, illegal_action_policy="fold", parallel=false))]
+    #[allow(clippy::too_many_arguments)]
+    fn py_new(n_games: usize, n_players: usize, stack: usize, blinds: (usize, usize), seed: u64, betting_structure: &str, raise_cap: usize, bet_fraction: Option<f64>, illegal_action_policy: &str, parallel: bool) -> PyResult<Self> {
+        if n_players < 2 {
+            return Err(PyErr::new::<PyValueError, _>("A table needs at least two players"));
+        }
+
+        let mut vec_game = PyPokerVecGame {
+            tables: Vec::new(),
+            players: (0..n_players).map(|i| Player { player_id: format!("Player {}", i + 1), balance: stack }).collect(),
+            minimum_bet: blinds.0,
+            config: TableConfig {
+                illegal_action_policy: parse_illegal_action_policy(illegal_action_policy)?,
+                betting_structure: parse_betting_structure(betting_structure, raise_cap)?,
+                forced_bets: ForcedBets { blinds: Some(blinds), ..ForcedBets::default() }
+            },
+            bet_fraction,
+            parallel
+        };
+        vec_game.seed_tables(n_games, seed);
+
+        Ok(vec_game)
+    }
+
+    /// Deal every table a new hand, seeding the tables again if `seed` is given.
+    /// Returns the observations and action masks.
+    #[pyo3(signature = (seed=None))]
+    fn reset<'py>(&mut self, py: Python<'py>, seed: Option<u64>) -> (&'py PyArray2<f32>, &'py PyArray2<bool>) {
+        match seed {
+            Some(seed) => self.seed_tables(self.tables.len(), seed),
+            None => {
+                for table in &mut self.tables {
+                    table.deal();
+                }
+            }
+        }
+
+        self.observe(py)
+    }
+
+    /// Step every table with the GIL released, returning the observations, each seat's chip delta for hands
+    /// that finished, which tables finished a hand, and the action masks.
+    ///
+    /// Every action is checked before any table moves, so an illegal action rejected by the table's policy
+    /// raises `IllegalActionError` and leaves every table as it was.
+    #[allow(clippy::type_complexity)]
+    fn step<'py>(&mut self, py: Python<'py>, actions: Vec<usize>) -> PyResult<(&'py PyArray2<f32>, &'py PyArray2<f32>, &'py PyArray1<bool>, &'py PyArray2<bool>)> {
+        if actions.len() != self.tables.len() {
+            return Err(PyErr::new::<PyValueError, _>(format!("Expected an action for each of the {} tables", self.tables.len())));
+        }
+        if actions.iter().any(|x| *x >= ActionKind::ALL.len()) {
+            return Err(PyErr::new::<PyValueError, _>("Actions must be indices into ACTION_MASK_KINDS"));
+        }
+
+        let (tables, bet_fraction, parallel) = (&mut self.tables, self.bet_fraction, self.parallel);
+        let results: Result<Vec<Option<Vec<isize>>>, IllegalAction> = py.allow_threads(|| {
+            let validated: Vec<Action> = if parallel {
+                tables.par_iter().zip(actions.par_iter()).map(|(table, action)| table.validate(*action, bet_fraction)).collect::<Result<_, _>>()?
+            } else {
+                tables.iter().zip(&actions).map(|(table, action)| table.validate(*action, bet_fraction)).collect::<Result<_, _>>()?
+            };
+
+            Ok(if parallel {
+                tables.par_iter_mut().zip(validated).map(|(table, action)| table.step(action)).collect()
+            } else {
+                tables.iter_mut().zip(validated).map(|(table, action)| table.step(action)).collect()
+            })
+        });
+        let results = results?;
+
+        let n_players = self.players.len();
+        let rewards = results.iter()
+            .flat_map(|x| x.clone().unwrap_or_else(|| vec![0; n_players]))
+            .map(|x| x as f32)
+            .collect();
+        let dones: Vec<bool> = results.iter().map(Option::is_some).collect();
+        let (observations, masks) = self.observe(py);
+
+        Ok((
+            observations,
+            Array2::from_shape_vec((self.tables.len(), n_players), rewards).expect("Every table has the same seats").into_pyarray(py),
+            dones.into_pyarray(py),
+            masks
+        ))
+    }
+
+    /// The seat of the player to act at every table.
+    fn current_seats<'py>(&self, py: Python<'py>) -> &'py PyArray1<usize> {
+        self.tables.iter()
+            .map(|x| x.environment().map_or(0, |x| x.current_player.seat))
+            .collect::<Vec<_>>()
+            .into_pyarray(py)
+    }
+
+    fn get_environment(&self, index: usize) -> PyResult<PyPokerEnvironment> {
+        self.tables.get(index)
+            .and_then(VecTable::environment)
+            .map(|x| x.into())
+            .ok_or_else(|| PyErr::new::<PyValueError, _>("There is no table at that index"))
+    }
+
+    fn __len__(&self) -> usize {
+        self.tables.len()
+    }
+}
+
+/// A recorded hand played again through the engine.
+#[pyclass]
+struct PyPokerReplay {
+    #[pyo3(get)]
+    hand_id: String,
+    // What the player saw before each recorded action, and the action they took
+    #[pyo3(get)]
+    steps: Vec<(PyPokerEnvironment, String)>,
+    // Every way the engine disagreed with the record, empty when the replay matches
+    #[pyo3(get)]
+    divergences: Vec<String>,
+    game: GameState<ChaCha12Rng>
+}
+
+#[pymethods]
+impl PyPokerReplay {
+    /// The replayed game, finished unless the replay diverged part way through.
+    fn game(&self) -> PyPokerGame {
+        PyPokerGame { game: self.game.clone() }
+    }
+}
+
+/// Replay every hand of a PokerStars hand history, or a single PHH hand, dealing any hidden cards from `seed`.
+#[pyfunction]
+#[pyo3(signature = (text, format="pokerstars", seed=0))]
+fn replay_hand_history(text: &str, format: &str, seed: u64) -> PyResult<Vec<PyPokerReplay>> {
+    let hands = match format.to_lowercase().as_str() {
+        "pokerstars" => game::parse_pokerstars(text)?,
+        "phh" => vec![game::parse_phh(text)?],
+        _ => return Err(PyErr::new::<PyValueError, _>("Hand history format must be one of \"pokerstars\" or \"phh\""))
+    };
+
+    hands.iter()
+        .map(|hand| {
+            let replay = hand.replay(ChaCha12Rng::seed_from_u64(seed))?;
+            Ok(PyPokerReplay {
+                hand_id: hand.hand_id.clone(),
+                steps: replay.steps.into_iter().map(|(environment, action)| (environment.into(), action.to_string())).collect(),
+                divergences: replay.divergences.iter().map(|x| x.to_string()).collect(),
+                game: replay.game
+            })
+        })
+        .collect()
+}
+
+/// Read cards written like "AhKd Qs" or as the engine shows them, like "HA SX".
+#[pyfunction]
+fn parse_cards(text: &str) -> PyResult<Vec<PyCard>> {
+    let cards = rules::parse_cards(text).map_err(|e| PyErr::new::<PyValueError, _>(e.to_string()))?;
+
+    Ok(cards.into_iter().map(PyCard).collect())
+}
+
+/// The best hand made from two hole cards and three to five board cards.
+#[pyfunction]
+#[pyo3(name = "evaluate")]
+fn evaluate_hand(hole: Vec<PyCardArg>, board: Vec<PyCardArg>) -> PyResult<PyHand> {
+    let (hands, board) = parse_hands_and_board(&[&hole], &board)?;
+    let (best, cards) = calculate_best_cards(hands[0], &board);
+
+    Ok(PyHand {
+        category: best.category().to_string(),
+        rank: HandRank::from(best).0,
+        cards: cards.into_iter().map(PyCard).collect()
+    })
+}
+
+/// 1 if the first hand wins on the board, -1 if the second does and 0 if they tie.
+#[pyfunction]
+fn compare_hands(first: Vec<PyCardArg>, second: Vec<PyCardArg>, board: Vec<PyCardArg>) -> PyResult<i8> {
+    let (hands, board) = parse_hands_and_board(&[&first, &second], &board)?;
+
+    Ok(calculate_best_hand(hands[0], &board).cmp(&calculate_best_hand(hands[1], &board)) as i8)
+}
+
+/// The equity of the hole cards against `opponents` random hands, or against one opponent for each of
+/// `ranges`. Deals are enumerated when there are few enough, and sampled from `seed` otherwise.
+#[pyfunction]
+#[pyo3(signature = (hole, board=vec![], dead=vec![], opponents=1, ranges=None, samples=10000, seed=0))]
+#[allow(clippy::too_many_arguments)]
+fn equity(py: Python, hole: Vec<PyCardArg>, board: Vec<PyCardArg>, dead: Vec<PyCardArg>, opponents: usize, ranges: Option<Vec<PyRangeArg>>, samples: usize, seed: u64) -> PyResult<PyEquity> {
+    let hand: [Card; 2] = parse_card_list(&hole)?
+        .try_into()
+        .map_err(|_| PyErr::new::<PyValueError, _>("A hand must be two cards"))?;
+    let board = parse_card_list(&board)?;
+    let dead = parse_card_list(&dead)?;
+    let opponents = parse_opponents(opponents, ranges)?;
+
+    let equity = py.allow_threads(|| calculate_equity(hand, &board, &dead, &opponents, samples, ChaCha12Rng::seed_from_u64(seed)))?;
+    Ok(equity.into())
+}
+
+/// The equity of a hand from the `hero` range, worked out like `equity`.
+#[pyfunction]
+#[pyo3(signature = (hero, board=vec![], dead=vec![], opponents=1, ranges=None, samples=10000, seed=0))]
+#[allow(clippy::too_many_arguments)]
+fn range_equity(py: Python, hero: PyRangeArg, board: Vec<PyCardArg>, dead: Vec<PyCardArg>, opponents: usize, ranges: Option<Vec<PyRangeArg>>, samples: usize, seed: u64) -> PyResult<PyEquity> {
+    let hero = hero.into_range()?;
+    let board = parse_card_list(&board)?;
+    let dead = parse_card_list(&dead)?;
+    let opponents = parse_opponents(opponents, ranges)?;
+
+    let equity = py.allow_threads(|| calculate_range_equity(&hero, &board, &dead, &opponents, samples, ChaCha12Rng::seed_from_u64(seed)))?;
+    Ok(equity.into())
+}
 
 /// A Python module implemented in Rust.
 #[pymodule]
-fn poker_environment(_py: Python, m: &PyModule) -> PyResult<()> {
+fn poker_environment(py: Python, m: &PyModule) -> PyResult<()> {
     m.add_class::<PyPokerPlayerInfo>()?;
+    m.add_class::<PyCard>()?;
+    m.add_class::<PyHand>()?;
+    m.add_class::<PyEquity>()?;
+    m.add_class::<PyRange>()?;
     m.add_class::<PyPokerGame>()?;
     m.add_class::<PyPokerDealtPlayer>()?;
     m.add_class::<PyPokerDealtPlayerVisible>()?;
     m.add_class::<PyPokerActionHistory>()?;
     m.add_class::<PyPokerEnvironment>()?;
+    m.add_class::<PyPokerPot>()?;
+    m.add_class::<PyPokerSession>()?;
+    m.add_class::<PyPokerLegalAction>()?;
+    m.add_class::<PyPokerReplay>()?;
+    m.add_class::<PyPokerVecGame>()?;
+    m.add_function(wrap_pyfunction!(replay_hand_history, m)?)?;
+    m.add_function(wrap_pyfunction!(parse_cards, m)?)?;
+    m.add_function(wrap_pyfunction!(evaluate_hand, m)?)?;
+    m.add_function(wrap_pyfunction!(compare_hands, m)?)?;
+    m.add_function(wrap_pyfunction!(equity, m)?)?;
+    m.add_function(wrap_pyfunction!(range_equity, m)?)?;
+    m.add("ACTION_MASK_KINDS", ActionKind::ALL.map(|x| x.to_string()).to_vec())?;
+    m.add("OBSERVATION_VERSION", OBSERVATION_VERSION)?;
+    m.add("OBSERVATION_SIZE", OBSERVATION_SIZE)?;
+    // Each part of an observation with where it starts and ends
+    m.add("OBSERVATION_LAYOUT", OBSERVATION_LAYOUT.iter()
+        .scan(0, |start, (name, length)| {
+            *start += length;
+            Some((name.to_string(), (*start - length, *start)))
+        })
+        .collect::<HashMap<String, (usize, usize)>>())?;
+    m.add("IllegalActionError", py.get_type::<IllegalActionError>())?;
 
     Ok(())
 }
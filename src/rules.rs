@@ -1,7 +1,11 @@
 mod card;
 mod hand;
 mod deck;
+mod range;
+pub mod evaluator;
+pub mod equity;
 
 pub use card::*;
 pub use hand::*;
-pub use deck::*;
\ No newline at end of file
+pub use deck::*;
+pub use range::*;
\ No newline at end of file
@@ -1,7 +1,10 @@
 use std::cmp::Ordering;
+use std::error::Error;
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use serde::{Deserialize, Serialize};
 
-#[derive(Ord, Eq, PartialEq, PartialOrd, Copy, Clone, Debug, Hash)]
+#[derive(Ord, Eq, PartialEq, PartialOrd, Copy, Clone, Debug, Hash, Serialize, Deserialize)]
 pub enum CardSuit {
     Hearts = 0,
     Diamonds = 1,
@@ -9,7 +12,7 @@ pub enum CardSuit {
     Spades = 3,
 }
 
-#[derive(Debug, Eq, PartialEq, PartialOrd, Ord, Copy, Clone, Hash)]
+#[derive(Debug, Eq, PartialEq, PartialOrd, Ord, Copy, Clone, Hash, Serialize, Deserialize)]
 pub enum CardValue {
     Ace = 14,
     Two = 2,
@@ -26,9 +29,54 @@ pub enum CardValue {
     King = 13,
 }
 
-#[derive(Debug, Copy, Clone, Hash)]
+impl CardSuit {
+    pub const ALL: [CardSuit; 4] = [CardSuit::Hearts, CardSuit::Diamonds, CardSuit::Clubs, CardSuit::Spades];
+}
+
+impl CardValue {
+    /// Every value from lowest to highest
+    pub const ALL: [CardValue; 13] = [
+        CardValue::Two,
+        CardValue::Three,
+        CardValue::Four,
+        CardValue::Five,
+        CardValue::Six,
+        CardValue::Seven,
+        CardValue::Eight,
+        CardValue::Nine,
+        CardValue::Ten,
+        CardValue::Jack,
+        CardValue::Queen,
+        CardValue::King,
+        CardValue::Ace,
+    ];
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub struct Card(pub CardSuit, pub CardValue);
 
+impl Card {
+    /// The card's place from 0 for the two of hearts to 51 for the ace of spades, ordered by value and then suit.
+    pub fn index(&self) -> usize {
+        4 * (self.1 as usize - 2) + self.0 as usize
+    }
+
+    pub fn from_index(index: usize) -> Option<Card> {
+        let value = *CardValue::ALL.get(index / 4)?;
+        Some(Card(CardSuit::ALL[index % 4], value))
+    }
+
+    /// The card in standard notation, value first with a lowercase suit, like "Ah" or "Td".
+    pub fn to_standard_notation(&self) -> String {
+        let value = match self.1 {
+            CardValue::Ten => "T".to_string(),
+            value => value.to_string()
+        };
+
+        format!("{}{}", value, self.0.to_string().to_lowercase())
+    }
+}
+
 impl Display for Card {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}{}", self.0, self.1)
@@ -61,14 +109,6 @@ impl Display for CardValue {
     }
 }
 
-impl Eq for Card {}
-
-impl PartialEq<Self> for Card {
-    fn eq(&self, other: &Self) -> bool {
-        matches!(self.cmp(other), Ordering::Equal)
-    }
-}
-
 impl PartialOrd<Self> for Card {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -88,3 +128,91 @@ impl Ord for Card {
         }
     }
 }
+
+/// Text that is not a card, value or suit.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CardParseError(pub String);
+
+impl Display for CardParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "\"{}\" is not a card", self.0)
+    }
+}
+
+impl Error for CardParseError {
+}
+
+impl FromStr for CardSuit {
+    type Err = CardParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "H" | "♥" => Ok(CardSuit::Hearts),
+            "D" | "♦" => Ok(CardSuit::Diamonds),
+            "C" | "♣" => Ok(CardSuit::Clubs),
+            "S" | "♠" => Ok(CardSuit::Spades),
+            _ => Err(CardParseError(s.to_string()))
+        }
+    }
+}
+
+impl FromStr for CardValue {
+    type Err = CardParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_uppercase().as_str() {
+            "A" => Ok(CardValue::Ace),
+            "K" => Ok(CardValue::King),
+            "Q" => Ok(CardValue::Queen),
+            "J" => Ok(CardValue::Jack),
+            // The engine writes tens as "X"
+            "T" | "X" | "10" => Ok(CardValue::Ten),
+            number => CardValue::ALL.into_iter()
+                .find(|x| *x != CardValue::Ten && x.to_string() == number)
+                .ok_or_else(|| CardParseError(s.to_string()))
+        }
+    }
+}
+
+/// Reads the engine's suit first format, like "HA" or "SX", and standard notation, like "Ah", "Th" or "10h".
+impl FromStr for Card {
+    type Err = CardParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Suits and values never share a symbol, so the suit can sit on either side
+        let text = s.trim();
+        let mut chars = text.chars();
+        let (first, last) = (chars.next(), chars.next_back());
+        let error = || CardParseError(s.to_string());
+
+        if let Some(Ok(suit)) = first.map(|x| x.to_string().parse::<CardSuit>()) {
+            let value = text[first.map_or(0, char::len_utf8)..].parse().map_err(|_| error())?;
+            Ok(Card(suit, value))
+        } else if let Some(Ok(suit)) = last.map(|x| x.to_string().parse::<CardSuit>()) {
+            let value = text[..text.len() - last.map_or(0, char::len_utf8)].parse().map_err(|_| error())?;
+            Ok(Card(suit, value))
+        } else {
+            Err(error())
+        }
+    }
+}
+
+/// Read a list of cards in either format, separated by spaces or commas or written together, like "AhKd Qs".
+pub fn parse_cards(text: &str) -> Result<Vec<Card>, CardParseError> {
+    let mut cards = Vec::new();
+    for word in text.split(|x: char| x.is_whitespace() || x == ',').filter(|x| !x.is_empty()) {
+        let symbols: Vec<char> = word.chars().collect();
+        let mut start = 0;
+        while start < symbols.len() {
+            // Every card takes two symbols, except a ten written as "10"
+            let (card, length) = [3, 2].into_iter()
+                .filter(|n| start + n <= symbols.len())
+                .find_map(|n| symbols[start..start + n].iter().collect::<String>().parse::<Card>().ok().map(|card| (card, n)))
+                .ok_or_else(|| CardParseError(word.to_string()))?;
+            cards.push(card);
+            start += length;
+        }
+    }
+
+    Ok(cards)
+}
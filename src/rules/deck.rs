@@ -1,13 +1,19 @@
-use std::collections::HashSet;
-use rand::distributions::{Distribution, Standard};
+use itertools::Itertools;
 use rand::prelude::ThreadRng;
+use rand::seq::SliceRandom;
 use rand::Rng;
 use super::{Card, CardValue, CardSuit};
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone)]
+/// A shuffled 52 card deck, dealt from the top.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Deck<R> where R: Rng + Sized {
     rng: R,
-    taboo_list: HashSet<Card>,
+    // Every card in dealing order, with everything before `next` already dealt
+    cards: Vec<Card>,
+    next: usize,
+    burn_cards: bool,
+    burnt: Vec<Card>,
 }
 
 impl Deck<ThreadRng> {
@@ -16,22 +22,82 @@ impl Deck<ThreadRng> {
     }
 }
 
+impl Default for Deck<ThreadRng> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl <R: Rng + Sized> Deck<R> {
-    pub fn new_with_rng(rng: R) -> Self {
+    pub fn new_with_rng(mut rng: R) -> Self {
+        let mut cards: Vec<Card> = CardSuit::ALL.into_iter()
+            .cartesian_product(CardValue::ALL)
+            .map(|(suit, value)| Card(suit, value))
+            .collect();
+        cards.shuffle(&mut rng);
+
         Self {
             rng,
-            taboo_list: HashSet::new(),
+            cards,
+            next: 0,
+            burn_cards: false,
+            burnt: Vec::with_capacity(3),
         }
     }
 
-    pub fn draw(&mut self) -> Card {
-        let card: Card = self.rng.gen();
+    /// A deck dealing `cards` first and in order, followed by the rest of the deck shuffled.
+    ///
+    /// Burn cards are drawn from the deck like any other, so with [`Deck::with_burn_cards`] they come out of
+    /// `cards` too. Use [`Deck::with_hands`] to set the hands and board with the burns left random.
+    pub fn new_stacked(rng: R, cards: &[Card]) -> Self {
+        assert!(cards.iter().all_unique(), "A stacked deck cannot hold the same card twice");
+
+        let mut deck = Self::new_with_rng(rng);
+        deck.cards.retain(|x| !cards.contains(x));
+        deck.cards.splice(0..0, cards.iter().copied());
+
+        deck
+    }
 
-        if self.taboo_list.insert(card) {
-            card
-        } else {
-            self.draw()
+    /// Burn a card before dealing each of the flop, turn and river.
+    pub fn with_burn_cards(mut self, burn_cards: bool) -> Self {
+        self.burn_cards = burn_cards;
+        self
+    }
+
+    /// Deal `hands` to the seats in order and `board` to the table, with a random hand for each `None`
+    /// and the rest of the deck random.
+    ///
+    /// The board is dealt straight after the hands, so `hands` needs an entry for every seat.
+    /// Burn cards fall between the streets, so this should come after [`Deck::with_burn_cards`].
+    pub fn with_hands(mut self, hands: &[Option<[Card; 2]>], board: &[Card]) -> Self {
+        let known: Vec<Card> = hands.iter().flatten().flatten().chain(board).copied().collect();
+        assert!(known.iter().all_unique(), "The same card cannot be dealt twice");
+        assert!(board.len() <= 5, "The board only has five cards");
+
+        let mut random = self.cards[self.next..].iter().copied().filter(|x| !known.contains(x));
+        let mut cards: Vec<Card> = self.cards[..self.next].to_vec();
+        for hand in hands {
+            cards.extend(hand.unwrap_or_else(|| [random.next(), random.next()].map(|x| x.expect("Deck ran out of cards"))));
+        }
+        for (street, n_cards) in [(0, 3), (3, 4), (4, 5)] {
+            if self.burn_cards {
+                cards.extend(random.next());
+            }
+            cards.extend(board.iter().take(n_cards).skip(street));
+            cards.extend(random.by_ref().take(n_cards.saturating_sub(board.len().max(street))));
         }
+        cards.extend(random);
+
+        self.cards = cards;
+        self
+    }
+
+    pub fn draw(&mut self) -> Card {
+        let card = *self.peek().expect("Deck ran out of cards");
+        self.next += 1;
+
+        card
     }
 
     pub fn draw_n<const N: usize>(&mut self) -> [Card; N] {
@@ -41,41 +107,43 @@ impl <R: Rng + Sized> Deck<R> {
             .try_into()
             .expect("Array should be of the correct size")
     }
-}
 
-impl Distribution<CardSuit> for Standard {
-    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> CardSuit {
-        match rng.gen_range(0..4) {
-            0 => CardSuit::Hearts,
-            1 => CardSuit::Diamonds,
-            2 => CardSuit::Clubs,
-            _ => CardSuit::Spades,
+    /// Deal the cards for the next street, burning one first if the deck burns cards.
+    pub fn deal_street<const N: usize>(&mut self) -> [Card; N] {
+        if self.burn_cards {
+            self.burn();
         }
+
+        self.draw_n()
     }
-}
 
-impl Distribution<CardValue> for Standard {
-    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> CardValue {
-        match rng.gen_range(2..15) {
-            2 => CardValue::Two,
-            3 => CardValue::Three,
-            4 => CardValue::Four,
-            5 => CardValue::Five,
-            6 => CardValue::Six,
-            7 => CardValue::Seven,
-            8 => CardValue::Eight,
-            9 => CardValue::Nine,
-            10 => CardValue::Ten,
-            11 => CardValue::Jack,
-            12 => CardValue::Queen,
-            13 => CardValue::King,
-            _ => CardValue::Ace,
-        }
+    pub fn burn(&mut self) -> Card {
+        let card = self.draw();
+        self.burnt.push(card);
+
+        card
+    }
+
+    /// Shuffle the cards that have not been dealt yet.
+    pub fn shuffle(&mut self) {
+        self.cards[self.next..].shuffle(&mut self.rng);
+    }
+
+    pub fn peek(&self) -> Option<&Card> {
+        self.cards.get(self.next)
+    }
+
+    /// The cards left in the deck, in the order they will be dealt.
+    pub fn remaining(&self) -> &[Card] {
+        &self.cards[self.next..]
+    }
+
+    /// The generator the deck shuffles with, for other random choices made from the same seed.
+    pub fn rng(&mut self) -> &mut R {
+        &mut self.rng
     }
-}
 
-impl Distribution<Card> for Standard {
-    fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Card {
-        Card(rng.gen(), rng.gen())
+    pub fn burnt(&self) -> &[Card] {
+        &self.burnt
     }
 }
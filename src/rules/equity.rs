@@ -0,0 +1,236 @@
+//! Win, tie and lose probabilities of a hand against opponents.
+//!
+//! Every way the rest of the board and the opponents' hands can fall is enumerated when there are few
+//! enough of them, and sampled from a seeded deck otherwise.
+
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use itertools::Itertools;
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::Rng;
+use super::{calculate_best_hand, Card, Deck, Hand, Range};
+
+/// Most deals that are enumerated rather than sampled
+pub const EXACT_LIMIT: usize = 2_000_000;
+
+// Most tries at each sampled deal before giving up on players whose hands keep clashing
+const MAX_ATTEMPTS: usize = 100;
+
+/// Who a hand is up against.
+#[derive(Debug, Clone)]
+pub enum Opponents {
+    // This many opponents, each holding any hand
+    Random(usize),
+    // One opponent for each range
+    Ranges(Vec<Range>)
+}
+
+/// How often a hand wins, ties and loses, weighted by how likely each deal is.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub struct Equity {
+    pub win: f64,
+    pub tie: f64,
+    pub lose: f64,
+    // Share of the pot the hand wins on average, with ties split between the tied hands
+    pub equity: f64,
+    // Deals evaluated
+    pub samples: usize,
+    // Whether every deal was enumerated rather than sampled
+    pub exact: bool
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum EquityError {
+    // The same card was given twice
+    DuplicateCard(Card),
+    TooManyCards,
+    NoOpponents,
+    // No deal gives every player a hand from their range
+    NoPossibleDeal,
+    // Too many deals to enumerate, with no samples asked for
+    NoSamples
+}
+
+impl Display for EquityError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EquityError::DuplicateCard(card) => write!(f, "{} is given twice", card.to_standard_notation()),
+            EquityError::TooManyCards => write!(f, "There are not enough cards left to deal every opponent and the board"),
+            EquityError::NoOpponents => write!(f, "There must be at least one opponent"),
+            EquityError::NoPossibleDeal => write!(f, "No deal gives every player a hand from their range"),
+            EquityError::NoSamples => write!(f, "There are too many deals to enumerate, so at least one sample is needed")
+        }
+    }
+}
+
+impl Error for EquityError {
+}
+
+// Running totals of the outcomes, weighted
+#[derive(Default)]
+struct Tally {
+    win: f64,
+    tie: f64,
+    lose: f64,
+    equity: f64,
+    total: f64,
+    samples: usize
+}
+
+impl Tally {
+    // The hero's hand first, then every opponent's
+    fn add(&mut self, hands: &[Hand], weight: f64) {
+        let (hero, opponents) = hands.split_first().expect("The hero has a hand");
+        let best = opponents.iter().max().expect("There is at least one opponent");
+        if hero > best {
+            self.win += weight;
+            self.equity += weight;
+        } else if hero == best {
+            let tied = opponents.iter().filter(|x| *x == best).count();
+            self.tie += weight;
+            self.equity += weight / (tied + 1) as f64;
+        } else {
+            self.lose += weight;
+        }
+        self.total += weight;
+        self.samples += 1;
+    }
+
+    fn finish(self, exact: bool) -> Result<Equity, EquityError> {
+        if self.total <= 0.0 {
+            return Err(EquityError::NoPossibleDeal);
+        }
+
+        Ok(Equity {
+            win: self.win / self.total,
+            tie: self.tie / self.total,
+            lose: self.lose / self.total,
+            equity: self.equity / self.total,
+            samples: self.samples,
+            exact
+        })
+    }
+}
+
+// Cards as bits by their index, to check hands for clashes quickly
+fn mask(cards: &[Card]) -> u64 {
+    cards.iter().fold(0, |mask, card| mask | 1 << card.index())
+}
+
+/// The equity of `hand` on a board of up to five cards, with `dead` cards out of the deck.
+///
+/// Deals are enumerated when there are at most [`EXACT_LIMIT`] of them, and otherwise `samples` deals
+/// are drawn from a deck shuffled by `rng`, of which there must be at least one.
+pub fn calculate_equity<R: Rng + Sized>(hand: [Card; 2], board: &[Card], dead: &[Card], opponents: &Opponents, samples: usize, rng: R) -> Result<Equity, EquityError> {
+    if let Some(card) = hand.iter().chain(board).chain(dead).duplicates().next() {
+        return Err(EquityError::DuplicateCard(*card));
+    }
+
+    calculate_range_equity(&Range::from_combos([(hand, 1.0)]), board, dead, opponents, samples, rng)
+}
+
+/// The equity of a hand from the `hero` range against the opponents, worked out like [`calculate_equity`].
+pub fn calculate_range_equity<R: Rng + Sized>(hero: &Range, board: &[Card], dead: &[Card], opponents: &Opponents, samples: usize, rng: R) -> Result<Equity, EquityError> {
+    let known: Vec<Card> = board.iter().chain(dead).copied().collect();
+    if let Some(card) = known.iter().duplicates().next() {
+        return Err(EquityError::DuplicateCard(*card));
+    }
+    if board.len() > 5 {
+        return Err(EquityError::TooManyCards);
+    }
+
+    // Every player's possible hands, the hero's first, leaving out those holding a known card
+    let opponents = match opponents {
+        Opponents::Random(n) => vec![Range::any(); *n],
+        Opponents::Ranges(ranges) => ranges.clone()
+    };
+    if opponents.is_empty() {
+        return Err(EquityError::NoOpponents);
+    }
+    let players: Vec<Range> = [hero.clone()].into_iter()
+        .chain(opponents)
+        .map(|x| x.without(&known))
+        .collect();
+    if players.iter().any(|x| x.is_empty()) {
+        return Err(EquityError::NoPossibleDeal);
+    }
+    let missing = 5 - board.len();
+    if known.len() + 2 * players.len() + missing > 52 {
+        return Err(EquityError::TooManyCards);
+    }
+
+    // The known cards are dealt first, leaving the rest of the deck to deal from
+    let mut deck = Deck::new_stacked(rng, &known);
+    for _ in &known {
+        deck.draw();
+    }
+    let deals = players.iter()
+        .try_fold(n_combinations(deck.remaining().len(), missing), |deals, range| deals.checked_mul(range.len()));
+
+    let mut tally = Tally::default();
+    if deals.is_some_and(|x| x <= EXACT_LIMIT) {
+        for completion in deck.remaining().iter().copied().combinations(missing) {
+            let full_board: Vec<Card> = board.iter().chain(&completion).copied().collect();
+            enumerate(&players, mask(&completion), &full_board, &mut Vec::with_capacity(players.len()), 1.0, &mut tally);
+        }
+
+        return tally.finish(true);
+    }
+    if samples == 0 {
+        return Err(EquityError::NoSamples);
+    }
+
+    let distributions: Vec<WeightedIndex<f64>> = players.iter()
+        .map(|range| WeightedIndex::new(range.combos().iter().map(|x| x.1)).expect("Every hand in a range has a positive weight"))
+        .collect();
+    // Deals where players' hands clash are dealt again, which keeps the rest in proportion
+    for _ in 0..samples.saturating_mul(MAX_ATTEMPTS) {
+        if tally.samples == samples {
+            break;
+        }
+        let mut used = 0;
+        let mut hands = Vec::with_capacity(players.len());
+        for (range, distribution) in players.iter().zip(&distributions) {
+            let (hand, _) = range.combos()[distribution.sample(deck.rng())];
+            if mask(&hand) & used != 0 {
+                break;
+            }
+            used |= mask(&hand);
+            hands.push(hand);
+        }
+        if hands.len() < players.len() {
+            continue;
+        }
+
+        deck.shuffle();
+        let full_board: Vec<Card> = board.iter()
+            .chain(deck.remaining().iter().filter(|x| mask(&[**x]) & used == 0).take(missing))
+            .copied()
+            .collect();
+        let hands: Vec<Hand> = hands.iter().map(|x| calculate_best_hand(*x, &full_board)).collect();
+        tally.add(&hands, 1.0);
+    }
+
+    tally.finish(false)
+}
+
+// Every way to give the players left a hand, given the cards already used
+fn enumerate(players: &[Range], used: u64, board: &[Card], hands: &mut Vec<Hand>, weight: f64, tally: &mut Tally) {
+    let Some((range, rest)) = players.split_first() else {
+        tally.add(hands, weight);
+        return;
+    };
+
+    for (hand, hand_weight) in range.combos() {
+        let hand_mask = mask(hand);
+        if hand_mask & used == 0 {
+            hands.push(calculate_best_hand(*hand, board));
+            enumerate(rest, used | hand_mask, board, hands, weight * hand_weight, tally);
+            hands.pop();
+        }
+    }
+}
+
+fn n_combinations(n: usize, k: usize) -> usize {
+    (0..k).fold(1, |total, i| total * (n - i) / (i + 1))
+}
@@ -0,0 +1,206 @@
+//! Table driven hand evaluation.
+//!
+//! Every distinct five card hand falls into one of 7462 equivalence classes. The tables below map
+//! any 5, 6 or 7 cards straight to the class of the best five card hand they contain, so evaluating
+//! a showdown is a handful of bit operations and two array lookups.
+//!
+//! Hands holding five or more cards of one suit are looked up by that suit's 13 bit value mask (no
+//! full house or four of a kind can hide alongside a flush in seven cards). Every other hand is
+//! looked up by the multiset of its values, indexed with a minimal perfect hash over the per value
+//! counts.
+
+use std::sync::OnceLock;
+use itertools::Itertools;
+use super::{rank_values, Card, CardValue, Hand};
+use serde::{Deserialize, Serialize};
+
+/// Number of distinct five card hand classes
+pub const HAND_CLASSES: usize = 7462;
+
+const MIN_CARDS: usize = 5;
+const MAX_CARDS: usize = 7;
+const VALUE_COUNT: usize = 13;
+
+/// The strength of a hand as a single comparable number.
+///
+/// Ranks run from 1 (seven high) to [`HAND_CLASSES`] (a royal flush). Two sets of cards share a rank
+/// exactly when their best hands tie.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub struct HandRank(pub u16);
+
+impl From<HandRank> for Hand {
+    fn from(value: HandRank) -> Self {
+        tables().hands[value.0 as usize - 1]
+    }
+}
+
+impl From<Hand> for HandRank {
+    fn from(value: Hand) -> Self {
+        HandRank(tables().rank_of(value))
+    }
+}
+
+/// Rank the best five card hand within 5, 6 or 7 distinct cards.
+pub fn evaluate(cards: &[Card]) -> HandRank {
+    assert!((MIN_CARDS..=MAX_CARDS).contains(&cards.len()), "Can only evaluate between 5 and 7 cards");
+
+    let tables = tables();
+    let mut suit_masks = [0usize; 4];
+    let mut counts = [0u8; VALUE_COUNT];
+
+    for &Card(suit, value) in cards {
+        let value_index = value_index(value);
+        suit_masks[suit as usize] |= 1 << value_index;
+        counts[value_index] += 1;
+    }
+
+    if let Some(&flush_mask) = suit_masks.iter().find(|x| x.count_ones() as usize >= MIN_CARDS) {
+        HandRank(tables.flush[flush_mask])
+    } else {
+        HandRank(tables.no_flush[cards.len() - MIN_CARDS][tables.hash(&counts, cards.len())])
+    }
+}
+
+struct Tables {
+    // Every hand class, weakest first
+    hands: Vec<Hand>,
+    // Best rank for each suited value mask with at least five bits set
+    flush: Vec<u16>,
+    // Best rank for each value multiset, one table per card count
+    no_flush: [Vec<u16>; MAX_CARDS - MIN_CARDS + 1],
+    // Number of ways to place `r` cards over the values from `i` upwards
+    combinations: [[usize; MAX_CARDS + 1]; VALUE_COUNT + 1],
+}
+
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+
+    TABLES.get_or_init(Tables::build)
+}
+
+fn value_index(value: CardValue) -> usize {
+    value as usize - 2
+}
+
+impl Tables {
+    fn build() -> Self {
+        let mut combinations = [[0; MAX_CARDS + 1]; VALUE_COUNT + 1];
+        combinations[VALUE_COUNT][0] = 1;
+        for i in (0..VALUE_COUNT).rev() {
+            for r in 0..=MAX_CARDS {
+                combinations[i][r] = (0..=r.min(4)).map(|c| combinations[i + 1][r - c]).sum();
+            }
+        }
+
+        let no_flush_hands = value_multisets(MIN_CARDS)
+            .into_iter()
+            .map(|counts| rank_values(counts_to_values(&counts), false));
+        let flush_hands = (0..VALUE_COUNT)
+            .combinations(MIN_CARDS)
+            .map(|indices| rank_values(mask_to_values(indices_to_mask(&indices)), true));
+        let hands: Vec<Hand> = no_flush_hands.chain(flush_hands).sorted().collect();
+        assert_eq!(hands.len(), HAND_CLASSES);
+
+        let mut tables = Tables {
+            hands,
+            flush: vec![0; 1 << VALUE_COUNT],
+            no_flush: Default::default(),
+            combinations,
+        };
+
+        // Suited hands, building each mask's best rank from the masks one card smaller
+        for n_cards in MIN_CARDS..=MAX_CARDS {
+            for indices in (0..VALUE_COUNT).combinations(n_cards) {
+                let mask = indices_to_mask(&indices);
+                tables.flush[mask] = if n_cards == MIN_CARDS {
+                    tables.rank_of(rank_values(mask_to_values(mask), true))
+                } else {
+                    indices.iter().map(|i| tables.flush[mask & !(1 << i)]).max()
+                        .expect("Mask cannot be empty")
+                };
+            }
+        }
+
+        // Unsuited hands, likewise removing one card at a time
+        for n_cards in MIN_CARDS..=MAX_CARDS {
+            let mut table = vec![0; combinations[0][n_cards]];
+            for mut counts in value_multisets(n_cards) {
+                table[tables.hash(&counts, n_cards)] = if n_cards == MIN_CARDS {
+                    tables.rank_of(rank_values(counts_to_values(&counts), false))
+                } else {
+                    let mut best = 0;
+                    for i in 0..VALUE_COUNT {
+                        if counts[i] > 0 {
+                            counts[i] -= 1;
+                            best = best.max(tables.no_flush[n_cards - 1 - MIN_CARDS][tables.hash(&counts, n_cards - 1)]);
+                            counts[i] += 1;
+                        }
+                    }
+                    best
+                };
+            }
+            tables.no_flush[n_cards - MIN_CARDS] = table;
+        }
+
+        tables
+    }
+
+    fn rank_of(&self, hand: Hand) -> u16 {
+        let position = self.hands.binary_search(&hand)
+            .expect("Every hand belongs to a class");
+
+        position as u16 + 1
+    }
+
+    // Minimal perfect hash of a value multiset of `n_cards` cards
+    fn hash(&self, counts: &[u8; VALUE_COUNT], n_cards: usize) -> usize {
+        let mut remaining = n_cards;
+        let mut index = 0;
+
+        for (i, &count) in counts.iter().enumerate() {
+            for c in 0..count as usize {
+                index += self.combinations[i + 1][remaining - c];
+            }
+            remaining -= count as usize;
+        }
+
+        index
+    }
+}
+
+fn value_multisets(n_cards: usize) -> Vec<[u8; VALUE_COUNT]> {
+    fn fill(i: usize, remaining: usize, counts: &mut [u8; VALUE_COUNT], out: &mut Vec<[u8; VALUE_COUNT]>) {
+        if i == VALUE_COUNT {
+            if remaining == 0 {
+                out.push(*counts);
+            }
+            return;
+        }
+        for c in 0..=remaining.min(4) {
+            counts[i] = c as u8;
+            fill(i + 1, remaining - c, counts, out);
+        }
+        counts[i] = 0;
+    }
+
+    let mut out = Vec::new();
+    fill(0, n_cards, &mut [0; VALUE_COUNT], &mut out);
+    out
+}
+
+fn indices_to_mask(indices: &[usize]) -> usize {
+    indices.iter().fold(0, |mask, i| mask | 1 << i)
+}
+
+fn mask_to_values(mask: usize) -> [CardValue; 5] {
+    counts_to_values(&std::array::from_fn(|i| (mask >> i & 1) as u8))
+}
+
+fn counts_to_values(counts: &[u8; VALUE_COUNT]) -> [CardValue; 5] {
+    counts.iter()
+        .zip(CardValue::ALL)
+        .flat_map(|(&count, value)| std::iter::repeat_n(value, count as usize))
+        .collect::<Vec<_>>()
+        .try_into()
+        .expect("Counts must describe exactly five cards")
+}
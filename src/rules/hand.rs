@@ -1,158 +1,148 @@
-use std::cmp::{min, Ordering};
-use std::iter::zip;
+use std::cmp::Reverse;
 use itertools::Itertools;
 use super::{Card, CardValue};
-
-#[derive(Copy, Clone, Debug)]
+use super::evaluator::evaluate;
+use serde::{Deserialize, Serialize};
+
+/// A ranked five card poker hand.
+///
+/// Every variant carries all five card values ordered by significance, so hands of the same
+/// category are compared kicker by kicker. Suits never take part in the comparison.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
 #[repr(isize)]
 pub enum Hand {
-    // High Card
-    StraightFlush(Card) = 9,
-    // FourKind, HighCard
-    FourOfAKind(CardValue, Card) = 8,
-    // Big House, Small House
-    FullHouse(CardValue, CardValue) = 7,
-    // High Card
-    Flush(Card) = 6,
-    // High Card
-    Straight(Card) = 5,
-    // Kind, High Card
-    ThreeOfAKind(CardValue, Card) = 4,
-    // Pair, Pair, High Card
-    TwoPair(CardValue, CardValue, Card) = 3,
-    // Pair, High Card
-    Pair(CardValue, Card) = 2,
-    HighCard(Card) = 1,
+    // Straight, highest first (the wheel is led by its Five)
+    StraightFlush([CardValue; 5]) = 9,
+    // FourKind x4, Kicker
+    FourOfAKind([CardValue; 5]) = 8,
+    // Big House x3, Small House x2
+    FullHouse([CardValue; 5]) = 7,
+    // Flush, highest first
+    Flush([CardValue; 5]) = 6,
+    // Straight, highest first (the wheel is led by its Five)
+    Straight([CardValue; 5]) = 5,
+    // Kind x3, Kickers
+    ThreeOfAKind([CardValue; 5]) = 4,
+    // High Pair x2, Low Pair x2, Kicker
+    TwoPair([CardValue; 5]) = 3,
+    // Pair x2, Kickers
+    Pair([CardValue; 5]) = 2,
+    // Cards, highest first
+    HighCard([CardValue; 5]) = 1,
 }
 
 impl Hand {
-    fn discriminant(&self) -> isize {
-        unsafe { *(self as *const Self as *const isize) }
+    /// The five card values of this hand, ordered by significance.
+    pub fn values(&self) -> [CardValue; 5] {
+        let (Hand::StraightFlush(values)
+        | Hand::FourOfAKind(values)
+        | Hand::FullHouse(values)
+        | Hand::Flush(values)
+        | Hand::Straight(values)
+        | Hand::ThreeOfAKind(values)
+        | Hand::TwoPair(values)
+        | Hand::Pair(values)
+        | Hand::HighCard(values)) = self;
+
+        *values
     }
-}
 
-impl PartialEq<Self> for Hand {
-    fn eq(&self, other: &Self) -> bool {
-        matches!(self.cmp(other), Ordering::Equal)
+    /// The name of the hand's category, like "Full House".
+    pub fn category(&self) -> &'static str {
+        match self {
+            Hand::StraightFlush(_) => "Straight Flush",
+            Hand::FourOfAKind(_) => "Four of a Kind",
+            Hand::FullHouse(_) => "Full House",
+            Hand::Flush(_) => "Flush",
+            Hand::Straight(_) => "Straight",
+            Hand::ThreeOfAKind(_) => "Three of a Kind",
+            Hand::TwoPair(_) => "Two Pair",
+            Hand::Pair(_) => "Pair",
+            Hand::HighCard(_) => "High Card",
+        }
     }
 }
 
-impl Eq for Hand {}
-
-impl PartialOrd for Hand {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
-}
+pub(crate) fn rank_values(values: [CardValue; 5], is_flush: bool) -> Hand {
+    // Group the values by how often they appear, the biggest (then highest) group first
+    let groups: Vec<(CardValue, usize)> = values
+        .iter()
+        .counts()
+        .into_iter()
+        .map(|(value, count)| (*value, count))
+        .sorted_by_key(|&(value, count)| Reverse((count, value)))
+        .collect();
 
-impl Ord for Hand {
-    fn cmp(&self, other: &Self) -> Ordering {
-        match (self, other) {
-            (Hand::StraightFlush(a_v), Hand::StraightFlush(b_v))
-            | (Hand::Flush(a_v), Hand::Flush(b_v))
-            | (Hand::Straight(a_v), Hand::Straight(b_v))
-            | (Hand::HighCard(a_v), Hand::HighCard(b_v)) => a_v.cmp(b_v),
-            (Hand::FourOfAKind(a_v, a_high), Hand::FourOfAKind(b_v, b_high))
-            | (Hand::ThreeOfAKind(a_v, a_high), Hand::ThreeOfAKind(b_v, b_high))
-            | (Hand::Pair(a_v, a_high), Hand::Pair(b_v, b_high)) => {
-                let cmp = a_v.cmp(b_v);
-
-                match cmp {
-                    Ordering::Equal => a_high.cmp(b_high),
-                    a => a,
-                }
-            }
-            (Hand::FullHouse(aa_v, ab_v), Hand::FullHouse(ba_v, bb_v)) => match aa_v.cmp(ba_v) {
-                Ordering::Equal => ab_v.cmp(bb_v),
-                a => a,
-            },
-            (Hand::TwoPair(aa_v, ab_v, a_high), Hand::TwoPair(ba_v, bb_v, b_high)) => {
-                match aa_v.cmp(ba_v) {
-                    Ordering::Equal => match ab_v.cmp(bb_v) {
-                        Ordering::Equal => a_high.cmp(b_high),
-                        a => a,
-                    },
-                    a => a,
-                }
-            }
-            (a, b) => a.discriminant().cmp(&b.discriminant()),
+    let mut ordered: [CardValue; 5] = groups
+        .iter()
+        .flat_map(|&(value, count)| std::iter::repeat_n(value, count))
+        .collect::<Vec<_>>()
+        .try_into()
+        .expect("A hand must contain exactly five cards");
+
+    let is_straight = groups.len() == 5 && {
+        if ordered == [CardValue::Ace, CardValue::Five, CardValue::Four, CardValue::Three, CardValue::Two] {
+            ordered.rotate_left(1);
+            true
+        } else {
+            ordered[0] as isize - ordered[4] as isize == 4
         }
+    };
+
+    match (is_straight, is_flush, groups[0].1, groups[1].1) {
+        (true, true, _, _) => Hand::StraightFlush(ordered),
+        (_, _, 4, _) => Hand::FourOfAKind(ordered),
+        (_, _, 3, 2) => Hand::FullHouse(ordered),
+        (_, true, _, _) => Hand::Flush(ordered),
+        (true, _, _, _) => Hand::Straight(ordered),
+        (_, _, 3, _) => Hand::ThreeOfAKind(ordered),
+        (_, _, 2, 2) => Hand::TwoPair(ordered),
+        (_, _, 2, _) => Hand::Pair(ordered),
+        _ => Hand::HighCard(ordered),
     }
 }
 
-pub fn calculate_hand(mut hand: Vec<Card>) -> Hand {
-    assert!(!hand.is_empty());
+pub fn calculate_hand(hand: Vec<Card>) -> Hand {
+    assert_eq!(hand.len(), 5, "A hand must contain exactly five cards");
 
-    hand.sort();
+    let is_flush: bool = hand.iter().map(|x| x.0).all_equal();
+    let values: [CardValue; 5] = hand
+        .iter()
+        .map(|x| x.1)
+        .collect::<Vec<_>>()
+        .try_into()
+        .expect("Hand length was checked above");
 
-    let is_straight_ace_high: bool = zip(hand.iter().take(hand.len() - 1), hand.iter().skip(1))
-        .all(|(a, b)| a.1 as isize + 1 == b.1 as isize);
-    let hand_ace_low = hand.iter().map(|x| match x.1 {
-        CardValue::Ace => 1,
-        a => a as isize,
-    });
-    // TODO This could be way more efficient
-    let is_straight_ace_low = zip(
-        hand_ace_low.clone().take(hand_ace_low.len() - 1),
-        hand_ace_low.skip(1),
-    )
-    .all(|(a, b)| a + 1 == b);
-    let is_straight = is_straight_ace_low || is_straight_ace_high;
+    rank_values(values, is_flush)
+}
 
-    let is_flush: bool = hand.iter().map(|x| x.0).all_equal();
+pub fn calculate_best_hand(hand: [Card; 2], table: &[Card]) -> Hand {
+    let mut all_cards = [hand[0]; 7];
+    let n_cards = table.len() + hand.len();
+    assert!(n_cards >= 5, "A best hand needs at least five cards");
 
-    let high_card: Card = *hand.last().expect("Hand cannot have been empty");
-
-    match (is_straight, is_flush, hand.len()) {
-        (true, true, 5) => Hand::StraightFlush(high_card),
-        (true, false, 5) => Hand::Straight(high_card),
-        (false, true, 5) => Hand::Flush(high_card),
-        (_, _, _hand_len) => {
-            let hand_values = hand.iter().counts_by(|c| c.1);
-            let mut hand_value_order: Vec<_> = hand_values
-                .into_iter()
-                .sorted_by_key(|(_, count)| *count)
-                .collect();
-
-            if let Some((first_highest_value, first_highest_count)) = hand_value_order.pop() {
-                match first_highest_count {
-                    1 => Hand::HighCard(high_card),
-                    2 => {
-                        if let Some((sec_highest_value, 2)) = hand_value_order.pop() {
-                            Hand::TwoPair(first_highest_value, sec_highest_value, high_card)
-                        } else {
-                            Hand::Pair(first_highest_value, high_card)
-                        }
-                    }
-                    3 => {
-                        if let Some((sec_highest_value, 2)) = hand_value_order.pop() {
-                            Hand::FullHouse(first_highest_value, sec_highest_value)
-                        } else {
-                            Hand::ThreeOfAKind(first_highest_value, high_card)
-                        }
-                    }
-                    _ => Hand::FourOfAKind(first_highest_value, high_card),
-                }
-            } else {
-                Hand::HighCard(high_card)
-            }
-        }
+    for (slot, card) in all_cards.iter_mut().zip(table.iter().chain(hand.iter())) {
+        *slot = *card;
     }
+
+    evaluate(&all_cards[..n_cards]).into()
 }
 
-pub fn calculate_best_hand(hand: [Card; 2], table: &Vec<Card>) -> Hand {
-    let all_cards: Vec<Card> = table
-        .iter()
+/// The best hand within the hole cards and the table, along with the five cards that make it,
+/// ordered like [`Hand::values`].
+pub fn calculate_best_cards(hand: [Card; 2], table: &[Card]) -> (Hand, [Card; 5]) {
+    let best = calculate_best_hand(hand, table);
+    let mut cards = table.iter()
         .chain(hand.iter())
-        .map(|x| x.to_owned())
-        .collect();
-    let hand_size = min(all_cards.len(), 5);
-
-    let permutations = all_cards.into_iter().permutations(hand_size);
+        .copied()
+        .combinations(5)
+        .find(|x| calculate_hand(x.clone()) == best)
+        .expect("The best hand is made of five of the cards");
+
+    let ordered = best.values().map(|value| {
+        let position = cards.iter().position(|x| x.1 == value).expect("Every value of the hand is one of its cards");
+        cards.swap_remove(position)
+    });
 
-    permutations
-        .into_iter()
-        .map(|h| calculate_hand(h))
-        .max()
-        .expect("Permutations cannot be empty")
+    (best, ordered)
 }
@@ -0,0 +1,178 @@
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use itertools::Itertools;
+use super::{parse_cards, Card, CardSuit, CardValue};
+use serde::{Deserialize, Serialize};
+
+/// A weighted set of hole card combinations a player might hold.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct Range {
+    // Each combination once, highest card first, with a weight above zero
+    combos: Vec<([Card; 2], f64)>
+}
+
+/// Text that is not a hand range.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RangeParseError(pub String);
+
+impl Display for RangeParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "\"{}\" is not a hand range", self.0)
+    }
+}
+
+impl Error for RangeParseError {
+}
+
+impl Range {
+    /// A range of the given combinations, where a combination given again takes its last weight.
+    pub fn from_combos(combos: impl IntoIterator<Item = ([Card; 2], f64)>) -> Self {
+        let mut range = Range::default();
+        for (hand, weight) in combos {
+            range.insert(hand, weight);
+        }
+
+        range
+    }
+
+    /// Every one of the 1326 hands, equally likely.
+    pub fn any() -> Self {
+        Self::from_combos(CardSuit::ALL.into_iter()
+            .cartesian_product(CardValue::ALL)
+            .map(|(suit, value)| Card(suit, value))
+            .tuple_combinations()
+            .map(|(first, second)| ([first, second], 1.0)))
+    }
+
+    pub fn combos(&self) -> &[([Card; 2], f64)] {
+        &self.combos
+    }
+
+    pub fn len(&self) -> usize {
+        self.combos.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.combos.is_empty()
+    }
+
+    /// The range without the combinations holding any of `cards`, like the board or a known hand.
+    pub fn without(&self, cards: &[Card]) -> Self {
+        Range {
+            combos: self.combos.iter()
+                .filter(|(hand, _)| !hand.iter().any(|x| cards.contains(x)))
+                .copied()
+                .collect()
+        }
+    }
+
+    fn insert(&mut self, hand: [Card; 2], weight: f64) {
+        let hand = if hand[0] < hand[1] { [hand[1], hand[0]] } else { hand };
+        self.combos.retain(|x| x.0 != hand);
+        if weight > 0.0 {
+            self.combos.push((hand, weight));
+        }
+    }
+}
+
+/// Reads standard range notation, like "QQ+, AKs, KQo, 22-55, A2s+, KTo-K8o, AhKh", with an optional
+/// weight after each part, like "AKo:0.5".
+impl FromStr for Range {
+    type Err = RangeParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut range = Range::default();
+        for part in s.split([',', ' ', '\t', '\n']).filter(|x| !x.is_empty()) {
+            let error = || RangeParseError(part.to_string());
+            let (hands, weight) = match part.split_once(':') {
+                Some((hands, weight)) => (hands, weight.parse::<f64>().ok().filter(|x| x.is_finite() && *x >= 0.0).ok_or_else(error)?),
+                None => (part, 1.0)
+            };
+
+            for hand in parse_hands(hands).ok_or_else(error)? {
+                range.insert(hand, weight);
+            }
+        }
+
+        Ok(range)
+    }
+}
+
+// Whether a hand class is suited, offsuit or either
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Suitedness {
+    Suited,
+    Offsuit,
+    Any
+}
+
+// The values and suitedness of a class like "AKs" or "TT", highest value first
+fn parse_class(text: &str) -> Option<(CardValue, CardValue, Suitedness)> {
+    let mut chars = text.chars();
+    let first: CardValue = chars.next()?.to_string().parse().ok()?;
+    let second: CardValue = chars.next()?.to_string().parse().ok()?;
+    let suitedness = match chars.next().map(|x| x.to_ascii_lowercase()) {
+        None => Suitedness::Any,
+        Some('s') if first != second => Suitedness::Suited,
+        Some('o') if first != second => Suitedness::Offsuit,
+        _ => return None
+    };
+    if chars.next().is_some() {
+        return None;
+    }
+
+    Some((first.max(second), first.min(second), suitedness))
+}
+
+// Every combination of the two values with the right suits
+fn class_combos(high: CardValue, low: CardValue, suitedness: Suitedness) -> Vec<[Card; 2]> {
+    if high == low {
+        return CardSuit::ALL.into_iter()
+            .tuple_combinations()
+            .map(|(first, second)| [Card(first, high), Card(second, low)])
+            .collect();
+    }
+
+    CardSuit::ALL.into_iter()
+        .cartesian_product(CardSuit::ALL)
+        .filter(|(first, second)| match suitedness {
+            Suitedness::Suited => first == second,
+            Suitedness::Offsuit => first != second,
+            Suitedness::Any => true
+        })
+        .map(|(first, second)| [Card(first, high), Card(second, low)])
+        .collect()
+}
+
+// The hands of one part of a range, without its weight
+fn parse_hands(text: &str) -> Option<Vec<[Card; 2]>> {
+    let between = |from: CardValue, to: CardValue| CardValue::ALL.into_iter().filter(move |x| (from.min(to)..=from.max(to)).contains(x));
+
+    if let Some(class) = text.strip_suffix('+') {
+        // Pairs go up to aces, other hands raise the lower card up to one below the higher
+        let (high, low, suitedness) = parse_class(class)?;
+        let classes: Vec<(CardValue, CardValue)> = if high == low {
+            between(low, CardValue::Ace).map(|x| (x, x)).collect()
+        } else {
+            between(low, high).filter(|x| *x != high).map(|x| (high, x)).collect()
+        };
+        Some(classes.into_iter().flat_map(|(high, low)| class_combos(high, low, suitedness)).collect())
+    } else if let Some((from, to)) = text.split_once('-') {
+        let (from, to) = (parse_class(from)?, parse_class(to)?);
+        let classes: Vec<(CardValue, CardValue)> = match (from, to) {
+            ((high, low, _), (to_high, to_low, _)) if high == low && to_high == to_low => between(high, to_high).map(|x| (x, x)).collect(),
+            ((high, low, _), (to_high, to_low, _)) if high == to_high && low != high && to_low != to_high => between(low, to_low).map(|x| (high, x)).collect(),
+            _ => return None
+        };
+        if from.2 != to.2 {
+            return None;
+        }
+        Some(classes.into_iter().flat_map(|(high, low)| class_combos(high, low, from.2)).collect())
+    } else if let Some((high, low, suitedness)) = parse_class(text) {
+        Some(class_combos(high, low, suitedness))
+    } else {
+        let hand: [Card; 2] = parse_cards(text).ok()?.try_into().ok()?;
+        (hand[0] != hand[1]).then_some(vec![hand])
+    }
+}
@@ -1,12 +1,27 @@
+use itertools::Itertools;
 use rand::{Rng, SeedableRng, thread_rng};
 use rand::prelude::StdRng;
-use crate::rules::calculate_best_hand;
+use rand_chacha::ChaCha12Rng;
+use crate::rules::evaluator::{evaluate, HandRank, HAND_CLASSES};
+use crate::rules::equity::{calculate_equity, calculate_range_equity, EquityError, Opponents};
+use crate::rules::{calculate_best_cards, calculate_best_hand, calculate_hand, parse_cards, Card, CardSuit, CardValue, Deck, Hand, Range};
 use crate::game::GameState;
 use crate::game::Action;
 use crate::game::Player;
+use crate::game::{distribute_pots, parse_phh, parse_pokerstars, BettingStructure, Divergence, BlindSchedule, DealtPlayer, ForcedBet, ForcedBets, IllegalActionPolicy, LegalAction, ActionKind, Session, Showdown, TableConfig, info_set_hash, OBSERVATION_SIZE};
 
 const SEED: u64 = 12;
 
+// Players named "Player 1" onwards, all with the same balance
+fn players(n: usize, balance: usize) -> Vec<Player> {
+    (1..=n)
+        .map(|i| Player {
+            player_id: format!("Player {}", i),
+            balance
+        })
+        .collect()
+}
+
 #[test]
 fn test_all_call() {
     let test_players = vec![
@@ -36,13 +51,13 @@ fn test_all_call() {
         }
     ];
     let test_players_len = test_players.len();
-    let mut game = GameState::new_with_players(StdRng::seed_from_u64(SEED), test_players, 2);
+    let mut game = GameState::new_with_players(StdRng::seed_from_u64(SEED), test_players, 2, 0);
 
     let mut x = 0;
     while {
         match game {
             GameState::BettingRound(betting_round) => {
-                game = betting_round.update_state(Action::Call);
+                game = betting_round.update_state(Action::Call).unwrap();
 
                 true
             },
@@ -87,7 +102,9 @@ fn test_random() {
         // }
     ];
 
-    let mut game = GameState::new_with_players(StdRng::seed_from_u64(SEED), test_players, 2);
+    // Raises the player cannot afford are folded, as they were before illegal actions could be rejected
+    let config = TableConfig { illegal_action_policy: IllegalActionPolicy::Fold, ..TableConfig::default() };
+    let mut game = GameState::new_with_deck(Deck::new_with_rng(StdRng::seed_from_u64(SEED)), test_players, 2, 0, config);
     let mut rng = thread_rng();
 
     while {
@@ -104,7 +121,7 @@ fn test_random() {
 
                 println!("---\n{}\n\n{:?}", betting_round.get_environment(), action);
 
-                game = betting_round.update_state(action);
+                game = betting_round.update_state(action).unwrap();
 
                 true
             },
@@ -125,15 +142,18 @@ fn test_random() {
             print!("{}|{}({:?})\t", player.hand[0], player.hand[1], player.balance);
         }
         println!();
-        let hand_list: Vec<_> = sd.players.0.iter().map(|p| calculate_best_hand(p.hand, &sd.table)).collect();
-        for hand in &hand_list {
-            print!("{:?}\t", hand);
+        if sd.table.len() == 5 {
+            let hand_list: Vec<_> = sd.players.0.iter().map(|p| calculate_best_hand(p.hand, &sd.table)).collect();
+            for hand in &hand_list {
+                print!("{:?}\t", hand);
+            }
+            println!();
+            println!("---\nBest Hand: {:?}", &hand_list.iter().max().unwrap());
         }
-        println!();
-        println!("---\nBest Hand: {:?}", &hand_list.iter().max().unwrap());
         println!("---\nNew Players: {:?}", sd.players);
         assert_eq!(sd.players.0.iter().map(|x| x.balance.1).sum::<usize>(), 0);
-        assert_eq!(sd.players.0.iter().map(|x| x.expectation).sum::<usize>(), 0);
+        assert_eq!(sd.pots.iter().map(|x| x.amount).sum::<usize>(), sd.bet.0);
+        assert_eq!(sd.pots.iter().flat_map(|x| x.winners.iter().map(|(_, won)| won)).sum::<usize>(), sd.bet.0);
         assert_eq!(sd.players.0.iter().map(|x| x.balance.0).sum::<usize>() + sd.players.1.iter().map(|x| x.balance).sum::<usize>(), 4 * 20)
     }
 }
@@ -167,28 +187,1280 @@ fn test_fold_bar_one() {
         }
     ];
     let test_players_len = test_players.len();
-    let mut game = GameState::new_with_players(StdRng::seed_from_u64(SEED), test_players, 2);
+    let mut game = GameState::new_with_players(StdRng::seed_from_u64(SEED), test_players, 2, 0);
 
     for _ in 0..(test_players_len - 1) {
         match game {
             GameState::BettingRound(betting_round) => {
-                game = betting_round.update_state(Action::Fold);
+                game = betting_round.update_state(Action::Fold).unwrap();
             },
             _ => panic!("Game finished before everyone folded!")
         }
     }
-    while {
-        match game {
-            GameState::BettingRound(betting_round) => {
-                game = betting_round.update_state(Action::Fold);
+    assert!(matches!(game, GameState::Finished(_)), "Game did not immediately finish after everyone folded!");
 
-                true
-            },
-            _ => false
+    println!("Finished!")
+}
+
+#[test]
+fn test_kickers_break_ties() {
+    use CardSuit::*;
+    use CardValue::*;
+
+    let table = vec![Card(Hearts, Ace), Card(Spades, Ace), Card(Clubs, Nine), Card(Diamonds, Seven), Card(Hearts, Four)];
+
+    let queen_kicker = calculate_best_hand([Card(Clubs, King), Card(Diamonds, Queen)], &table);
+    let jack_kicker = calculate_best_hand([Card(Spades, King), Card(Clubs, Jack)], &table);
+
+    assert_eq!(queen_kicker, Hand::Pair([Ace, Ace, King, Queen, Nine]));
+    assert!(queen_kicker > jack_kicker);
+}
+
+#[test]
+fn test_best_cards() {
+    let table = ["HA", "SA", "C9", "D7", "HK"].map(card);
+    let (hand, cards) = calculate_best_cards([card("CK"), card("DQ")], &table);
+    assert_eq!(hand, Hand::TwoPair([CardValue::Ace, CardValue::Ace, CardValue::King, CardValue::King, CardValue::Queen]));
+    assert_eq!(hand.category(), "Two Pair");
+    assert_eq!(cards.map(|x| x.1), hand.values());
+    assert!(cards.iter().all_unique());
+
+    // The flush is made from the suited cards, not the higher offsuit ace
+    let table = ["H2", "H9", "HJ", "SA", "H5"].map(card);
+    let (hand, cards) = calculate_best_cards([card("HK"), card("CA")], &table);
+    assert_eq!(hand.category(), "Flush");
+    assert_eq!(cards, ["HK", "HJ", "H9", "H5", "H2"].map(card));
+}
+
+#[test]
+fn test_suits_do_not_break_ties() {
+    use CardSuit::*;
+    use CardValue::*;
+
+    let table = vec![Card(Hearts, Two), Card(Spades, Eight), Card(Clubs, Nine), Card(Diamonds, Jack), Card(Hearts, Four)];
+
+    let hearts = calculate_best_hand([Card(Hearts, King), Card(Hearts, Queen)], &table);
+    let spades = calculate_best_hand([Card(Spades, King), Card(Spades, Queen)], &table);
+
+    assert_eq!(hearts, spades);
+}
+
+#[test]
+fn test_hand_categories() {
+    use CardSuit::*;
+    use CardValue::*;
+
+    let wheel = calculate_hand(vec![Card(Hearts, Ace), Card(Spades, Two), Card(Clubs, Three), Card(Diamonds, Four), Card(Hearts, Five)]);
+    let six_high = calculate_hand(vec![Card(Hearts, Six), Card(Spades, Two), Card(Clubs, Three), Card(Diamonds, Four), Card(Hearts, Five)]);
+    let full_house = calculate_hand(vec![Card(Hearts, Two), Card(Spades, Two), Card(Clubs, Three), Card(Diamonds, Three), Card(Hearts, Three)]);
+    let flush = calculate_hand(vec![Card(Hearts, Two), Card(Hearts, Nine), Card(Hearts, Three), Card(Hearts, Jack), Card(Hearts, King)]);
+
+    assert_eq!(wheel, Hand::Straight([Five, Four, Three, Two, Ace]));
+    assert!(six_high > wheel);
+    assert_eq!(full_house, Hand::FullHouse([Three, Three, Three, Two, Two]));
+    assert!(full_house > flush);
+    assert!(flush > six_high);
+}
+
+#[test]
+fn test_split_pot_on_tie() {
+    use CardSuit::*;
+    use CardValue::*;
+
+    let table = vec![Card(Hearts, Ace), Card(Hearts, King), Card(Hearts, Queen), Card(Hearts, Jack), Card(Hearts, Ten)];
+    let players = vec![
+        DealtPlayer {
+            player_id: "Player 1".to_string(),
+            seat: 0,
+            balance: (0, 5),
+            hand: [Card(Clubs, Two), Card(Clubs, Three)]
+        },
+        DealtPlayer {
+            player_id: "Player 2".to_string(),
+            seat: 1,
+            balance: (0, 5),
+            hand: [Card(Spades, Two), Card(Spades, Three)]
+        }
+    ];
+    let folded = vec![
+        DealtPlayer {
+            player_id: "Player 3".to_string(),
+            seat: 2,
+            balance: (10, 1),
+            hand: [Card(Spades, Four), Card(Spades, Five)]
+        }
+    ];
+
+    let (players, pots) = distribute_pots(&table, players, &folded, 0);
+
+    assert_eq!(pots.len(), 1);
+    assert_eq!(pots[0].amount, 11);
+    assert_eq!(players[0].balance.0 + players[1].balance.0, 11);
+    assert!(players[0].balance.0.abs_diff(players[1].balance.0) <= 1);
+}
+
+#[test]
+fn test_side_pots() {
+    use CardSuit::*;
+    use CardValue::*;
+
+    let table = vec![Card(Hearts, Two), Card(Spades, Seven), Card(Clubs, Nine), Card(Diamonds, Jack), Card(Hearts, Four)];
+    let players = vec![
+        // Short stack all in with the best hand
+        DealtPlayer {
+            player_id: "Short".to_string(),
+            seat: 0,
+            balance: (0, 10),
+            hand: [Card(Clubs, Ace), Card(Diamonds, Ace)]
+        },
+        DealtPlayer {
+            player_id: "Runner Up".to_string(),
+            seat: 1,
+            balance: (0, 50),
+            hand: [Card(Spades, King), Card(Hearts, King)]
+        },
+        DealtPlayer {
+            player_id: "Worst".to_string(),
+            seat: 2,
+            balance: (20, 60),
+            hand: [Card(Spades, Three), Card(Hearts, Five)]
+        }
+    ];
+    let folded = vec![
+        DealtPlayer {
+            player_id: "Folder".to_string(),
+            seat: 3,
+            balance: (0, 20),
+            hand: [Card(Clubs, Queen), Card(Diamonds, Queen)]
+        }
+    ];
+
+    let (players, pots) = distribute_pots(&table, players, &folded, 0);
+
+    // Main pot: 10 from everyone, first side pot: 40 from the three deep players (10 of it folded),
+    // second side pot: 10 uncalled from the deepest stack
+    assert_eq!(pots.iter().map(|x| x.amount).collect::<Vec<_>>(), vec![40, 90, 10]);
+    assert_eq!(pots[0].winners, vec![("Short".to_string(), 40)]);
+    assert_eq!(pots[1].eligible, vec!["Runner Up".to_string(), "Worst".to_string()]);
+    assert_eq!(pots[1].winners, vec![("Runner Up".to_string(), 90)]);
+    assert_eq!(pots[2].winners, vec![("Worst".to_string(), 10)]);
+    assert_eq!(players.iter().map(|x| x.balance.0).collect::<Vec<_>>(), vec![40, 90, 30]);
+}
+
+#[test]
+fn test_evaluator_matches_brute_force() {
+    let mut rng = StdRng::seed_from_u64(SEED);
+    let all_cards: Vec<Card> = CardSuit::ALL.into_iter()
+        .cartesian_product(CardValue::ALL)
+        .map(|(suit, value)| Card(suit, value))
+        .collect();
+
+    for n_cards in 5..=7 {
+        for _ in 0..2000 {
+            let cards: Vec<Card> = rand::seq::index::sample(&mut rng, all_cards.len(), n_cards)
+                .into_iter()
+                .map(|i| all_cards[i])
+                .collect();
+
+            let brute_force = cards.iter().copied()
+                .combinations(5)
+                .map(calculate_hand)
+                .max()
+                .unwrap();
+
+            assert_eq!(Hand::from(evaluate(&cards)), brute_force, "Cards: {:?}", cards);
+            assert_eq!(HandRank::from(brute_force), evaluate(&cards));
         }
-    } {
-        panic!("Game did not immediately finish after everyone folded!")
     }
+}
 
-    println!("Finished!")
-}
\ No newline at end of file
+#[test]
+fn test_evaluator_rank_bounds() {
+    use CardSuit::*;
+    use CardValue::*;
+
+    let royal_flush = [Card(Spades, Ace), Card(Spades, King), Card(Spades, Queen), Card(Spades, Jack), Card(Spades, Ten)];
+    let seven_high = [Card(Spades, Seven), Card(Hearts, Five), Card(Spades, Four), Card(Clubs, Three), Card(Spades, Two)];
+
+    assert_eq!(evaluate(&royal_flush), HandRank(HAND_CLASSES as u16));
+    assert_eq!(evaluate(&seven_high), HandRank(1));
+}
+
+#[test]
+fn test_deck_order() {
+    let mut deck = Deck::new_with_rng(StdRng::seed_from_u64(SEED));
+    let order = deck.remaining().to_vec();
+
+    assert_eq!(order.len(), 52);
+    assert_eq!(order.iter().unique().count(), 52);
+    assert_eq!(order, Deck::new_with_rng(StdRng::seed_from_u64(SEED)).remaining());
+
+    assert_eq!(deck.peek(), Some(&order[0]));
+    assert_eq!(deck.draw(), order[0]);
+    assert_eq!(deck.draw_n::<3>(), [order[1], order[2], order[3]]);
+    assert_eq!(deck.remaining(), &order[4..]);
+}
+
+#[test]
+fn test_burn_cards() {
+    let test_players = players(3, 20);
+    let deck = Deck::new_with_rng(StdRng::seed_from_u64(SEED)).with_burn_cards(true);
+    let order = deck.remaining().to_vec();
+    let mut game = GameState::new_with_deck(deck, test_players, 2, 0, TableConfig::default());
+
+    while let GameState::BettingRound(betting_round) = game {
+        game = betting_round.update_state(Action::Call).unwrap();
+    }
+
+    // Six hole cards, then a burn before each of the flop, turn and river
+    let GameState::Finished(sd) = game else { unreachable!() };
+    assert_eq!(sd.table, vec![order[7], order[8], order[9], order[11], order[13]]);
+}
+
+fn current_seat(game: &GameState<StdRng>) -> usize {
+    match game {
+        GameState::BettingRound(betting_round) => betting_round.get_environment().current_player.seat,
+        GameState::Finished(_) => panic!("Game finished unexpectedly!")
+    }
+}
+
+#[test]
+fn test_blinds_follow_button() {
+    let test_players = players(4, 20);
+    let mut game = GameState::new_with_players(StdRng::seed_from_u64(SEED), test_players, 2, 1);
+
+    let GameState::BettingRound(betting_round) = &game else { unreachable!() };
+    let blinds: Vec<_> = betting_round.get_players().into_iter().sorted_by_key(|x| x.seat).map(|x| x.balance.1).collect();
+    assert_eq!(blinds, vec![0, 0, 2, 4]);
+
+    // Under the gun opens, and the big blind closes the action before the flop
+    let mut seats = Vec::new();
+    for _ in 0..4 {
+        seats.push(current_seat(&game));
+        let GameState::BettingRound(betting_round) = game else { unreachable!() };
+        game = betting_round.update_state(Action::Call).unwrap();
+    }
+    assert_eq!(seats, vec![0, 1, 2, 3]);
+
+    // The small blind opens on the flop
+    assert_eq!(current_seat(&game), 2);
+}
+
+#[test]
+fn test_heads_up_blinds() {
+    let test_players = players(2, 20);
+    let mut game = GameState::new_with_players(StdRng::seed_from_u64(SEED), test_players, 2, 1);
+
+    let GameState::BettingRound(betting_round) = &game else { unreachable!() };
+    let blinds: Vec<_> = betting_round.get_players().into_iter().sorted_by_key(|x| x.seat).map(|x| x.balance.1).collect();
+    assert_eq!(blinds, vec![4, 2]);
+
+    // The button is the small blind and acts first before the flop, but last after it
+    assert_eq!(current_seat(&game), 1);
+    for _ in 0..2 {
+        let GameState::BettingRound(betting_round) = game else { unreachable!() };
+        game = betting_round.update_state(Action::Call).unwrap();
+    }
+    assert_eq!(current_seat(&game), 0);
+}
+
+#[test]
+fn test_button_skips_busted_players() {
+    let player = |player_id: &str, balance: usize| Player {
+        player_id: player_id.to_string(),
+        balance
+    };
+    let showdown = Showdown {
+        players: (Vec::new(), vec![player("D", 10), player("A", 10), player("C", 0), player("B", 0)]),
+        bet: (0, 0),
+        pots: Vec::new(),
+        table: Vec::new(),
+        history: Vec::new(),
+        seats: vec!["A".to_string(), "B".to_string(), "C".to_string(), "D".to_string()],
+        button: 0,
+        stacks: vec![10; 4],
+        hands: Vec::new(),
+        blinds: (1, 2),
+        betting_structure: BettingStructure::NoLimit
+    };
+
+    let (players, button) = showdown.next_hand();
+
+    assert_eq!(players.iter().map(|x| x.player_id.as_str()).collect::<Vec<_>>(), vec!["A", "D"]);
+    assert_eq!(button, 1);
+}
+
+#[test]
+fn test_blind_schedule() {
+    let default_schedule = BlindSchedule::default();
+    assert_eq!((1..=12).map(|x| default_schedule.minimum_bet(x)).collect::<Vec<_>>(), vec![2, 2, 2, 2, 3, 3, 4, 4, 5, 6, 6, 7]);
+
+    let levels = BlindSchedule::Levels { minimum_bets: vec![1, 2, 5], hands_per_level: 2 };
+    assert_eq!((1..=8).map(|x| levels.minimum_bet(x)).collect::<Vec<_>>(), vec![1, 1, 2, 2, 5, 5, 5, 5]);
+}
+
+#[test]
+fn test_session_plays_to_a_winner() {
+    let test_players = players(4, 20);
+    let mut session = Session::new(StdRng::seed_from_u64(SEED), test_players, BlindSchedule::Fixed(2), TableConfig::default());
+    let mut rng = StdRng::seed_from_u64(SEED);
+
+    while session.start_hand() {
+        let mut showdown = None;
+        while session.hand_in_progress() {
+            let can_raise = session.betting_round().expect("Hand is in progress").validate(Action::Raise(4)).is_ok();
+            let action = if can_raise && rng.gen_bool(0.2) { Action::Raise(4) } else { Action::Call };
+            showdown = session.advance(action).unwrap().cloned();
+        }
+
+        // Chips only ever move between the players
+        let showdown = showdown.expect("Every hand ends in a showdown");
+        assert_eq!(session.seats().iter().map(|x| x.balance).sum::<usize>(), 4 * 20);
+        assert!(session.seats().iter().all(|x| x.balance > 0));
+        assert_eq!(session.seats().len() + session.eliminated().len(), 4);
+        assert!(showdown.seats.len() >= session.seats().len());
+    }
+
+    assert!(session.is_finished());
+    assert_eq!(session.seats().len(), 1);
+    assert_eq!(session.eliminated().len(), 3);
+}
+
+#[test]
+fn test_illegal_raise_rejected() {
+    let test_players = players(3, 20);
+    let game = GameState::new_with_players(StdRng::seed_from_u64(SEED), test_players, 2, 0);
+    let GameState::BettingRound(betting_round) = game else { unreachable!() };
+
+    assert_eq!(betting_round.legal_actions(), vec![LegalAction::Fold, LegalAction::Call(4), LegalAction::Raise { minimum: 8, maximum: 20 }, LegalAction::AllIn(20)]);
+
+    for action in [Action::Raise(1), Action::Raise(17)] {
+        let error = betting_round.clone().update_state(action).err().expect("Raise should have been rejected");
+        assert_eq!(error.player_id, "Player 1");
+        assert_eq!(error.legal_actions, betting_round.legal_actions());
+    }
+
+    assert!(betting_round.update_state(Action::Raise(16)).is_ok());
+}
+
+#[test]
+fn test_illegal_action_policy() {
+    let test_players = players(3, 20);
+
+    for (illegal_action_policy, expected) in [(IllegalActionPolicy::Fold, "FOLD"), (IllegalActionPolicy::Call, "CALL")] {
+        let config = TableConfig { illegal_action_policy, ..TableConfig::default() };
+        let game = GameState::new_with_deck(Deck::new_with_rng(StdRng::seed_from_u64(SEED)), test_players.clone(), 2, 0, config);
+        let GameState::BettingRound(betting_round) = game else { unreachable!() };
+
+        let GameState::BettingRound(betting_round) = betting_round.update_state(Action::Raise(100)).unwrap() else { unreachable!() };
+        let history = betting_round.get_environment().game_history;
+        assert_eq!(history[0].last().unwrap().1.to_string(), expected);
+    }
+}
+
+#[test]
+fn test_environment_legal_actions() {
+    let test_players = vec![
+        Player {
+            player_id: "Short".to_string(),
+            balance: 5
+        },
+        Player {
+            player_id: "Deep".to_string(),
+            balance: 20
+        },
+        Player {
+            player_id: "Deeper".to_string(),
+            balance: 30
+        }
+    ];
+    let game = GameState::new_with_players(StdRng::seed_from_u64(SEED), test_players, 2, 0);
+    let GameState::BettingRound(betting_round) = game else { unreachable!() };
+
+    // The short stack can call the big blind but cannot afford a full raise on top of it
+    let environment = betting_round.get_environment();
+    assert_eq!(environment.current_player.player_id, "Short");
+    assert_eq!(environment.legal_actions, vec![LegalAction::Fold, LegalAction::Call(4), LegalAction::AllIn(5)]);
+    assert_eq!(environment.action_mask(), [true, false, true, false, false, true]);
+    assert_eq!(ActionKind::ALL.len(), environment.action_mask().len());
+}
+
+#[test]
+fn test_action_strings() {
+    for (text, action) in [
+        ("CALL", Action::Call),
+        ("FOLD", Action::Fold),
+        ("RAISE 4", Action::Raise(4)),
+        ("CHECK", Action::Check),
+        ("BET 6", Action::Bet(6)),
+        ("RAISE TO 12", Action::RaiseTo(12)),
+        ("ALLIN", Action::AllIn),
+        ("POST BIG_BLIND_ANTE 3", Action::Post(ForcedBet::BigBlindAnte, 3))
+    ] {
+        assert_eq!(Action::try_from(text.to_string()).unwrap(), action);
+        assert_eq!(action.to_string(), text);
+    }
+
+    assert_eq!(Action::try_from("all in".to_string()).unwrap(), Action::AllIn);
+    assert_eq!(Action::try_from("raise to 7".to_string()).unwrap(), Action::RaiseTo(7));
+    assert!(Action::try_from("BET".to_string()).is_err());
+    assert!(Action::try_from("RAISE TO lots".to_string()).is_err());
+}
+
+#[test]
+fn test_check_bet_raise_to_all_in() {
+    let test_players = players(3, 20);
+    let mut game = GameState::new_with_players(StdRng::seed_from_u64(SEED), test_players, 2, 0);
+
+    // Before the flop the big blind is the bet to raise, so there is nothing to check or open
+    let GameState::BettingRound(betting_round) = game else { unreachable!() };
+    assert!(betting_round.validate(Action::Check).is_err());
+    assert!(betting_round.validate(Action::Bet(8)).is_err());
+    game = betting_round.update_state(Action::RaiseTo(8)).unwrap();
+    for _ in 0..2 {
+        let GameState::BettingRound(betting_round) = game else { unreachable!() };
+        game = betting_round.update_state(Action::Call).unwrap();
+    }
+
+    // On the flop the first player can check or open the betting
+    let GameState::BettingRound(betting_round) = game else { unreachable!() };
+    let environment = betting_round.get_environment();
+    assert_eq!((environment.expected_bet, environment.street_bet), (8, 0));
+    assert_eq!(environment.legal_actions, vec![LegalAction::Fold, LegalAction::Check, LegalAction::Bet { minimum: 4, maximum: 12 }, LegalAction::AllIn(12)]);
+    assert!(betting_round.validate(Action::RaiseTo(4)).is_err());
+    // Calling nothing still works, and goes down in the history as a check
+    game = betting_round.update_state(Action::Call).unwrap();
+
+    for action in [Action::Bet(4), Action::AllIn] {
+        let GameState::BettingRound(betting_round) = game else { unreachable!() };
+        game = betting_round.update_state(action).unwrap();
+    }
+
+    // The all in raised the bet, so checking is no longer an option
+    let GameState::BettingRound(betting_round) = game else { unreachable!() };
+    assert_eq!((betting_round.get_environment().expected_bet, betting_round.street_bet()), (20, 12));
+    assert!(betting_round.validate(Action::Check).is_err());
+    game = betting_round.update_state(Action::Call).unwrap();
+
+    let GameState::BettingRound(betting_round) = game else { unreachable!() };
+    let history = betting_round.get_environment().game_history;
+    assert_eq!(history[1].iter().map(|x| x.1.to_string()).collect::<Vec<_>>(), vec!["CHECK", "BET 4", "ALLIN", "CALL"]);
+}
+
+fn structured_game(betting_structure: BettingStructure) -> GameState<StdRng> {
+    let test_players = players(3, 100);
+    let config = TableConfig { betting_structure, ..TableConfig::default() };
+
+    GameState::new_with_deck(Deck::new_with_rng(StdRng::seed_from_u64(SEED)), test_players, 2, 0, config)
+}
+
+#[test]
+fn test_no_limit_minimum_raise() {
+    let GameState::BettingRound(betting_round) = structured_game(BettingStructure::NoLimit) else { unreachable!() };
+    let GameState::BettingRound(betting_round) = betting_round.update_state(Action::RaiseTo(10)).unwrap() else { unreachable!() };
+
+    // The raise from 4 to 10 sets the next minimum raise to 6
+    let limits = betting_round.betting_limits();
+    assert_eq!((limits.minimum_raise, limits.maximum_raise, limits.raises_left), (6, None, None));
+    assert!(betting_round.validate(Action::RaiseTo(15)).is_err());
+    assert!(betting_round.validate(Action::Raise(5)).is_err());
+    assert!(betting_round.validate(Action::RaiseTo(16)).is_ok());
+    assert!(betting_round.validate(Action::AllIn).is_ok());
+}
+
+#[test]
+fn test_short_all_in_does_not_reopen_betting() {
+    let play = |big_blind_stack: usize| {
+        let mut test_players = players(3, 100);
+        test_players[2].balance = big_blind_stack;
+        let mut game = GameState::new_with_players(StdRng::seed_from_u64(SEED), test_players, 2, 0);
+        // The button raises to 8, the small blind calls and the big blind goes all in
+        for action in [Action::RaiseTo(8), Action::Call, Action::AllIn] {
+            let GameState::BettingRound(betting_round) = game else { unreachable!() };
+            game = betting_round.update_state(action).unwrap();
+        }
+        let GameState::BettingRound(betting_round) = game else { unreachable!() };
+        betting_round
+    };
+
+    // All in to 10 is short of the full raise to 12, so the button and small blind can only call it
+    let betting_round = play(10);
+    assert_eq!(current_seat(&GameState::BettingRound(betting_round.clone())), 0);
+    assert_eq!(betting_round.legal_actions(), vec![LegalAction::Fold, LegalAction::Call(2)]);
+    assert!(betting_round.clone().update_state(Action::RaiseTo(20)).is_err());
+    assert!(betting_round.clone().update_state(Action::AllIn).is_err());
+    let GameState::BettingRound(betting_round) = betting_round.update_state(Action::Call).unwrap() else { unreachable!() };
+    assert_eq!(betting_round.legal_actions(), vec![LegalAction::Fold, LegalAction::Call(2)]);
+
+    // All in to 12 is a full raise, which the button may raise again
+    let betting_round = play(12);
+    assert_eq!(betting_round.legal_actions(), vec![LegalAction::Fold, LegalAction::Call(4), LegalAction::Raise { minimum: 16, maximum: 100 }, LegalAction::AllIn(92)]);
+}
+
+#[test]
+fn test_pot_limit_maximum_raise() {
+    let GameState::BettingRound(betting_round) = structured_game(BettingStructure::PotLimit) else { unreachable!() };
+
+    // Calling 4 makes the pot 10, so the bet can go up by 10 to 14
+    assert_eq!(betting_round.legal_actions(), vec![LegalAction::Fold, LegalAction::Call(4), LegalAction::Raise { minimum: 8, maximum: 14 }]);
+    assert!(betting_round.validate(Action::AllIn).is_err());
+    let GameState::BettingRound(betting_round) = betting_round.update_state(Action::RaiseTo(14)).unwrap() else { unreachable!() };
+
+    // The small blind calls 12 into a pot of 20, and can raise by 32
+    assert_eq!(betting_round.betting_limits().maximum_raise, Some(32));
+    assert!(betting_round.validate(Action::RaiseTo(46)).is_ok());
+    assert!(betting_round.validate(Action::RaiseTo(47)).is_err());
+}
+
+#[test]
+fn test_fixed_limit_raise_cap() {
+    let mut game = structured_game(BettingStructure::FixedLimit { raise_cap: 4 });
+
+    // The big blind is the first of the four bets, leaving three raises of the small bet
+    for raise_to in [8, 12, 16] {
+        let GameState::BettingRound(betting_round) = game else { unreachable!() };
+        assert_eq!(betting_round.legal_actions()[2], LegalAction::Raise { minimum: raise_to, maximum: raise_to });
+        game = betting_round.update_state(Action::RaiseTo(raise_to)).unwrap();
+    }
+
+    let GameState::BettingRound(betting_round) = game else { unreachable!() };
+    assert_eq!(betting_round.betting_limits().raises_left, Some(0));
+    assert_eq!(betting_round.legal_actions(), vec![LegalAction::Fold, LegalAction::Call(8)]);
+    game = betting_round.update_state(Action::Call).unwrap();
+    let GameState::BettingRound(betting_round) = game else { unreachable!() };
+    game = betting_round.update_state(Action::Call).unwrap();
+
+    // The small bet carries on to the flop, and doubles into the big bet on the turn
+    let GameState::BettingRound(betting_round) = game else { unreachable!() };
+    assert_eq!(betting_round.legal_actions()[2], LegalAction::Bet { minimum: 4, maximum: 4 });
+    game = betting_round.update_state(Action::Check).unwrap();
+    for _ in 0..2 {
+        let GameState::BettingRound(betting_round) = game else { unreachable!() };
+        game = betting_round.update_state(Action::Check).unwrap();
+    }
+
+    let GameState::BettingRound(betting_round) = game else { unreachable!() };
+    assert_eq!(betting_round.get_environment().betting_limits.minimum_raise, 8);
+    assert_eq!(betting_round.legal_actions()[2], LegalAction::Bet { minimum: 8, maximum: 8 });
+}
+
+#[test]
+fn test_antes_and_straddle() {
+    let test_players = players(4, 100);
+    let forced_bets = ForcedBets { blinds: Some((5, 10)), ante: 1, big_blind_ante: 0, straddle: true };
+    let config = TableConfig { forced_bets, ..TableConfig::default() };
+    let mut game = GameState::new_with_deck(Deck::new_with_rng(StdRng::seed_from_u64(SEED)), test_players, 2, 0, config);
+
+    let GameState::BettingRound(betting_round) = &game else { unreachable!() };
+    let environment = betting_round.get_environment();
+    let posted: Vec<_> = environment.game_history[0].iter().map(|x| (x.0.as_str(), x.1)).collect();
+    assert_eq!(posted, vec![
+        ("Player 2", Action::Post(ForcedBet::Ante, 1)),
+        ("Player 3", Action::Post(ForcedBet::Ante, 1)),
+        ("Player 4", Action::Post(ForcedBet::Ante, 1)),
+        ("Player 1", Action::Post(ForcedBet::Ante, 1)),
+        ("Player 2", Action::Post(ForcedBet::SmallBlind, 5)),
+        ("Player 3", Action::Post(ForcedBet::BigBlind, 10)),
+        ("Player 4", Action::Post(ForcedBet::Straddle, 20))
+    ]);
+
+    // The player left of the straddle opens, and the antes do not count towards anyone's bet
+    assert_eq!((environment.pot, environment.expected_bet), (39, 20));
+    assert_eq!(environment.current_player.player_id, "Player 1");
+    assert_eq!(environment.current_player.balance, (99, 0));
+    assert_eq!(environment.legal_actions[1..3], [LegalAction::Call(20), LegalAction::Raise { minimum: 40, maximum: 99 }]);
+
+    while let GameState::BettingRound(betting_round) = game {
+        game = betting_round.update_state(Action::Call).unwrap();
+    }
+    let GameState::Finished(showdown) = game else { unreachable!() };
+    assert_eq!(showdown.pots.iter().map(|x| x.amount).sum::<usize>(), 84);
+    assert_eq!(showdown.next_hand().0.iter().map(|x| x.balance).sum::<usize>(), 400);
+}
+
+#[test]
+fn test_big_blind_ante() {
+    let test_players = players(3, 100);
+    let forced_bets = ForcedBets { big_blind_ante: 3, ..ForcedBets::default() };
+    let config = TableConfig { forced_bets, ..TableConfig::default() };
+    let game = GameState::new_with_deck(Deck::new_with_rng(StdRng::seed_from_u64(SEED)), test_players, 2, 0, config);
+
+    let GameState::BettingRound(betting_round) = game else { unreachable!() };
+    let environment = betting_round.get_environment();
+    assert_eq!(environment.game_history[0].iter().map(|x| x.1.to_string()).collect::<Vec<_>>(), vec!["POST SMALL_BLIND 2", "POST BIG_BLIND 4", "POST BIG_BLIND_ANTE 3"]);
+    assert_eq!((environment.pot, environment.expected_bet), (9, 4));
+
+    // The big blind only has the blind itself in their bet
+    let big_blind = environment.player_states.iter().find(|x| x.player_id == "Player 3").unwrap();
+    assert_eq!(big_blind.balance, (93, 4));
+
+    // Forced bets are only ever made by the table
+    assert!(betting_round.validate(Action::Post(ForcedBet::Ante, 4)).is_err());
+}
+
+fn card(text: &str) -> Card {
+    text.parse().unwrap()
+}
+
+#[test]
+fn test_card_parsing() {
+    let ace_of_hearts = Card(CardSuit::Hearts, CardValue::Ace);
+    for text in ["HA", "Ah", "ah", "AH", " hA "] {
+        assert_eq!(text.parse::<Card>(), Ok(ace_of_hearts));
+    }
+    for text in ["SX", "Ts", "10s", "s10", "10♠"] {
+        assert_eq!(text.parse::<Card>(), Ok(Card(CardSuit::Spades, CardValue::Ten)));
+    }
+    for text in ["", "1h", "Zz", "HH", "11s", "A", "AhK"] {
+        assert!(text.parse::<Card>().is_err(), "{} should not parse", text);
+    }
+    assert_eq!("t".parse::<CardValue>(), Ok(CardValue::Ten));
+    assert_eq!("♦".parse::<CardSuit>(), Ok(CardSuit::Diamonds));
+
+    // Both ways of writing every card read back as the same card
+    let deck: Vec<Card> = Deck::new_with_rng(StdRng::seed_from_u64(SEED)).remaining().to_vec();
+    for card in deck {
+        assert_eq!(card.to_string().parse::<Card>(), Ok(card));
+        assert_eq!(card.to_standard_notation().parse::<Card>(), Ok(card));
+    }
+
+    assert_eq!(parse_cards("AhKd Qs, 10c").unwrap(), ["AH", "KD", "QS", "XC"].map(card));
+    assert_eq!(parse_cards("HASX10d").unwrap(), ["HA", "SX", "DX"].map(card));
+    assert!(parse_cards("AhKd Q").is_err());
+}
+
+#[test]
+fn test_card_index() {
+    let mut deck: Vec<Card> = Deck::new_with_rng(StdRng::seed_from_u64(SEED)).remaining().to_vec();
+    deck.sort();
+
+    // Indices follow the order cards sort in
+    for (index, card) in deck.iter().enumerate() {
+        assert_eq!(card.index(), index);
+        assert_eq!(Card::from_index(index), Some(*card));
+    }
+    assert_eq!(card("H2").index(), 0);
+    assert_eq!(card("SA").index(), 51);
+    assert_eq!(Card::from_index(52), None);
+}
+
+#[test]
+fn test_stacked_side_pots() {
+    let test_players = [20, 50, 100].into_iter()
+        .enumerate()
+        .map(|(i, balance)| Player {
+            player_id: format!("Player {}", i + 1),
+            balance
+        })
+        .collect();
+    let hands = [Some([card("AS"), card("KS")]), Some([card("AH"), card("AD")]), Some([card("2C"), card("3D")])];
+    let board = [card("QS"), card("JS"), card("XS"), card("4H"), card("7C")];
+    let mut game = GameState::new_with_hands(StdRng::seed_from_u64(SEED), test_players, &hands, &board, 2, 0, TableConfig::default());
+
+    for action in [Action::AllIn, Action::AllIn, Action::Call] {
+        let GameState::BettingRound(betting_round) = game else { unreachable!() };
+        game = betting_round.update_state(action).unwrap();
+    }
+    while let GameState::BettingRound(betting_round) = game {
+        game = betting_round.update_state(Action::Check).unwrap();
+    }
+    let GameState::Finished(showdown) = game else { unreachable!() };
+
+    // The royal flush takes the main pot, the aces the side pot, and the rest goes back
+    assert_eq!(showdown.table, board);
+    assert_eq!(showdown.pots.iter().map(|x| (x.amount, x.winners.clone())).collect_vec(), vec![
+        (60, vec![("Player 1".to_string(), 60)]),
+        (60, vec![("Player 2".to_string(), 60)])
+    ]);
+    assert_eq!(showdown.next_hand().0.iter().map(|x| x.balance).collect_vec(), vec![60, 60, 50]);
+    assert_eq!(showdown.final_stacks(), vec![60, 60, 50]);
+    assert_eq!(showdown.chip_deltas(), vec![40, 10, -50]);
+}
+
+#[test]
+fn test_stacked_split_pot() {
+    let test_players = players(2, 50);
+    // Nobody holds a card that plays with the royal flush on the board
+    let cards = ["2H", "3H", "4D", "5D", "AS", "KS", "QS", "JS", "XS"].map(card);
+    let mut game = GameState::new_stacked(StdRng::seed_from_u64(SEED), test_players, &cards, 2, 0, TableConfig::default());
+
+    let GameState::BettingRound(betting_round) = &game else { unreachable!() };
+    assert_eq!(betting_round.get_environment().current_player.hand, [card("2H"), card("3H")]);
+    while let GameState::BettingRound(betting_round) = game {
+        game = betting_round.update_state(Action::Call).unwrap();
+    }
+    let GameState::Finished(showdown) = game else { unreachable!() };
+
+    assert_eq!(showdown.pots.len(), 1);
+    assert_eq!(showdown.pots[0].winners.iter().sorted().collect_vec(), [&("Player 1".to_string(), 4), &("Player 2".to_string(), 4)]);
+    assert_eq!(showdown.next_hand().0.iter().map(|x| x.balance).collect_vec(), vec![50, 50]);
+}
+
+#[test]
+fn test_partial_hands() {
+    // Seats without a hand get random ones rather than the board
+    let board = ["2C", "3C", "4C", "5C", "9D"].map(card);
+    let mut game = GameState::new_with_hands(StdRng::seed_from_u64(SEED), players(3, 50), &[Some([card("AS"), card("AH")])], &board, 2, 0, TableConfig::default());
+
+    let GameState::BettingRound(betting_round) = &game else { unreachable!() };
+    let hands: Vec<[Card; 2]> = betting_round.get_players().into_iter().sorted_by_key(|x| x.seat).map(|x| x.hand).collect();
+    assert_eq!(hands[0], [card("AS"), card("AH")]);
+    assert!(hands[1..].iter().flatten().all(|x| !board.contains(x) && *x != card("AS") && *x != card("AH")));
+    assert!(hands[1..].iter().flatten().all_unique());
+
+    while let GameState::BettingRound(betting_round) = game {
+        game = betting_round.update_state(Action::Call).unwrap();
+    }
+    let GameState::Finished(showdown) = game else { unreachable!() };
+    assert_eq!(showdown.table, board);
+}
+
+#[test]
+fn test_stacked_deck_with_burn_cards() {
+    let hands = [None, Some([card("AS"), card("AH")])];
+    let board = [card("2C"), card("3C"), card("4C"), card("5C")];
+    let mut deck = Deck::new_with_rng(StdRng::seed_from_u64(SEED)).with_burn_cards(true).with_hands(&hands, &board);
+
+    let random_hand: [Card; 2] = deck.draw_n();
+    assert!(random_hand.iter().all(|x| !board.contains(x) && *x != card("AS") && *x != card("AH")));
+    assert_eq!(deck.draw_n(), [card("AS"), card("AH")]);
+    assert_eq!(deck.deal_street(), [card("2C"), card("3C"), card("4C")]);
+    assert_eq!(deck.deal_street(), [card("5C")]);
+    let [river] = deck.deal_street();
+    assert_eq!(deck.burnt().len(), 3);
+    assert_eq!(deck.remaining().len() + 9 + 3, 52);
+    assert!(!board.contains(&river));
+
+    // A stacked deck is dealt exactly as given, burns included
+    let cards = ["AS", "AH", "KS", "KH", "2C", "3C", "4C", "5C"].map(card);
+    let mut deck = Deck::new_stacked(StdRng::seed_from_u64(SEED), &cards).with_burn_cards(true);
+    assert_eq!(deck.draw_n(), [card("AS"), card("AH")]);
+    assert_eq!(deck.draw_n(), [card("KS"), card("KH")]);
+    assert_eq!(deck.deal_street(), [card("3C"), card("4C"), card("5C")]);
+    assert_eq!(deck.burnt(), [card("2C")]);
+}
+
+#[test]
+fn test_serde_round_trip() {
+    let test_players = players(4, 50);
+    let mut game = GameState::new_with_players(ChaCha12Rng::seed_from_u64(SEED), test_players.clone(), 2, 1);
+
+    // ChaCha12 is the generator behind StdRng, so both deal the same cards
+    let GameState::BettingRound(std_game) = GameState::new_with_players(StdRng::seed_from_u64(SEED), test_players, 2, 1) else { unreachable!() };
+    let GameState::BettingRound(betting_round) = &game else { unreachable!() };
+    assert_eq!(std_game.get_players().iter().map(|x| x.hand).collect::<Vec<_>>(), betting_round.get_players().iter().map(|x| x.hand).collect::<Vec<_>>());
+
+    for action in [Action::RaiseTo(10), Action::Call, Action::Fold, Action::Call, Action::Check] {
+        let GameState::BettingRound(betting_round) = game else { unreachable!() };
+        game = betting_round.update_state(action).unwrap();
+    }
+
+    let json = serde_json::to_string(&game).unwrap();
+    let mut restored: GameState<ChaCha12Rng> = serde_json::from_str(&json).unwrap();
+    assert_eq!(serde_json::to_string(&restored).unwrap(), json);
+
+    // Both copies carry on identically, down to the shuffles of the remaining deck
+    let (original, copy) = loop {
+        match (game, restored) {
+            (GameState::BettingRound(original), GameState::BettingRound(copy)) => {
+                assert_eq!(format!("{}", original.get_environment()), format!("{}", copy.get_environment()));
+                game = original.update_state(Action::Call).unwrap();
+                restored = copy.update_state(Action::Call).unwrap();
+            }
+            (GameState::Finished(original), GameState::Finished(copy)) => break (original, copy),
+            _ => panic!("Both games should finish together")
+        }
+    };
+    assert_eq!(original.table, copy.table);
+    assert_eq!(original.next_hand().0.iter().map(|x| x.balance).collect::<Vec<_>>(), copy.next_hand().0.iter().map(|x| x.balance).collect::<Vec<_>>());
+
+    let mut deck = Deck::new_with_rng(ChaCha12Rng::seed_from_u64(SEED));
+    deck.draw_n::<5>();
+    let mut restored_deck: Deck<ChaCha12Rng> = serde_json::from_str(&serde_json::to_string(&deck).unwrap()).unwrap();
+    deck.shuffle();
+    restored_deck.shuffle();
+    assert_eq!(deck.remaining(), restored_deck.remaining());
+}
+
+#[test]
+fn test_pokerstars_hand_history() {
+    let test_players = players(3, 50);
+    let mut game = GameState::new_with_players(StdRng::seed_from_u64(SEED), test_players, 2, 0);
+    for action in [Action::RaiseTo(10), Action::Call, Action::Fold, Action::Check, Action::Bet(20), Action::Fold] {
+        let GameState::BettingRound(betting_round) = game else { unreachable!() };
+        game = betting_round.update_state(action).unwrap();
+    }
+    let GameState::Finished(showdown) = game else { unreachable!() };
+
+    let hand_history = showdown.to_pokerstars(7, "Test", 1_700_000_000, 0);
+    assert_eq!(hand_history.lines().collect::<Vec<_>>(), vec![
+        "PokerStars Hand #7: Hold'em No Limit (2/4) - 2023/11/14 22:13:20 UTC",
+        "Table 'Test' 3-max Seat #1 is the button",
+        "Seat 1: Player 1 (50 in chips)",
+        "Seat 2: Player 2 (50 in chips)",
+        "Seat 3: Player 3 (50 in chips)",
+        "Player 2: posts small blind 2",
+        "Player 3: posts big blind 4",
+        "*** HOLE CARDS ***",
+        "Dealt to Player 1 [6c 9c]",
+        "Player 1: raises 6 to 10",
+        "Player 2: calls 8",
+        "Player 3: folds",
+        "*** FLOP *** [5d Qc Jh]",
+        "Player 2: checks",
+        "Player 1: bets 20",
+        "Player 2: folds",
+        "Uncalled bet (20) returned to Player 1",
+        "Player 1 collected 24 from pot",
+        "Player 1: doesn't show hand",
+        "*** SUMMARY ***",
+        "Total pot 24 | Rake 0",
+        "Board [5d Qc Jh]",
+        "Seat 1: Player 1 (button) collected (24)",
+        "Seat 2: Player 2 (small blind) folded on the Flop",
+        "Seat 3: Player 3 (big blind) folded before Flop"
+    ]);
+}
+
+#[test]
+fn test_pokerstars_showdown() {
+    let test_players = vec![
+        Player {
+            player_id: "Short".to_string(),
+            balance: 10
+        },
+        Player {
+            player_id: "Deep".to_string(),
+            balance: 40
+        }
+    ];
+    let mut game = GameState::new_with_players(StdRng::seed_from_u64(SEED), test_players, 1, 0);
+    let GameState::BettingRound(betting_round) = game else { unreachable!() };
+    game = betting_round.update_state(Action::AllIn).unwrap();
+    while let GameState::BettingRound(betting_round) = game {
+        game = betting_round.update_state(Action::Call).unwrap();
+    }
+    let GameState::Finished(showdown) = game else { unreachable!() };
+
+    let hand_history = showdown.to_pokerstars(1, "Test", 0, 1);
+    let lines: Vec<&str> = hand_history.lines().collect();
+    assert_eq!(lines[0], "PokerStars Hand #1: Hold'em No Limit (1/2) - 1970/01/01 00:00:00 UTC");
+    assert!(lines.contains(&"Short: raises 8 to 10 and is all-in"));
+    assert!(lines.contains(&"Deep: calls 8"));
+    assert!(!lines.contains(&"Short: checks"));
+    assert_eq!(lines.iter().filter(|x| x.starts_with("Dealt to ")).count(), 1);
+    assert!(lines.iter().any(|x| x.starts_with("Dealt to Deep [")));
+    assert!(lines.iter().any(|x| x.starts_with("Seat 1: Short (button) (small blind) showed [")));
+    assert!(lines.iter().any(|x| x.starts_with("*** RIVER *** [")));
+    assert_eq!(lines.iter().filter(|x| x.contains(": shows [")).count(), 2);
+    assert!(lines.contains(&"Total pot 20 | Rake 0"));
+    assert!(!lines.iter().any(|x| x.starts_with("Uncalled bet")));
+}
+
+#[test]
+fn test_pokerstars_replay() {
+    let mut rng = StdRng::seed_from_u64(SEED);
+    let configs = [
+        TableConfig::default(),
+        TableConfig { betting_structure: BettingStructure::PotLimit, forced_bets: ForcedBets { ante: 1, ..ForcedBets::default() }, ..TableConfig::default() },
+        TableConfig { betting_structure: BettingStructure::FixedLimit { raise_cap: 4 }, forced_bets: ForcedBets { big_blind_ante: 4, straddle: true, ..ForcedBets::default() }, ..TableConfig::default() }
+    ];
+
+    for hand in 0..60 {
+        let config = configs[hand % configs.len()].clone();
+        let n_players = 2 + hand % 5;
+        let players: Vec<Player> = (1..=n_players)
+            .map(|i| Player {
+                player_id: format!("Player {}", i),
+                balance: rng.gen_range(10..200)
+            })
+            .collect();
+        let deck = Deck::new_with_rng(StdRng::seed_from_u64(rng.gen()));
+        let mut game = GameState::new_with_deck(deck, players, 2, hand % n_players, config);
+
+        // Random legal actions, with random bet sizes, and players who are all in going along
+        while let GameState::BettingRound(betting_round) = game {
+            let environment = betting_round.get_environment();
+            let legal_actions = environment.legal_actions;
+            let action = match legal_actions[rng.gen_range(0..legal_actions.len())] {
+                _ if environment.current_player.balance.0 == 0 => Action::Call,
+                LegalAction::Fold => Action::Fold,
+                LegalAction::Check | LegalAction::Call(_) => Action::Call,
+                LegalAction::Bet { minimum, maximum } => Action::Bet(rng.gen_range(minimum..=maximum)),
+                LegalAction::Raise { minimum, maximum } => Action::RaiseTo(rng.gen_range(minimum..=maximum)),
+                LegalAction::AllIn(_) => Action::AllIn
+            };
+            game = betting_round.update_state(action).unwrap();
+        }
+        let GameState::Finished(showdown) = game else { unreachable!() };
+
+        let recorded = parse_pokerstars(&showdown.to_pokerstars(hand as u64, "Test", 0, hand % n_players)).unwrap();
+        assert_eq!(recorded.len(), 1);
+        let replay = recorded[0].replay(StdRng::seed_from_u64(SEED)).unwrap();
+        assert!(replay.divergences.is_empty(), "Hand {} diverged: {}", hand, replay.divergences.iter().join(", "));
+
+        let GameState::Finished(replayed) = replay.game else { unreachable!() };
+        assert_eq!(replayed.table, showdown.table);
+        assert_eq!(replayed.next_hand().0.iter().map(|x| x.balance).collect_vec(), showdown.next_hand().0.iter().map(|x| x.balance).collect_vec());
+    }
+}
+
+#[test]
+fn test_pokerstars_import() {
+    let hand_history = "\u{feff}PokerStars Hand #245678901234: Hold'em No Limit ($0.01/$0.02 USD) - 2023/05/01 12:00:00 ET
+Table 'Alcyone IV' 6-max Seat #4 is the button
+Seat 1: Villain ($2.12 in chips)
+Seat 2: Hero ($2 in chips)
+Seat 4: Button Guy ($1.50 in chips)
+Seat 6: Away ($0.50 in chips) is sitting out
+Villain: posts small blind $0.01
+Hero: posts big blind $0.02
+*** HOLE CARDS ***
+Dealt to Hero [Ah Kh]
+Button Guy: raises $0.04 to $0.06
+Villain: folds
+Hero: raises $0.12 to $0.18
+Button Guy: calls $0.12
+*** FLOP *** [Kd 7s 2c]
+Hero: bets $0.20
+Button Guy: calls $0.20
+Hero said, \"nh\"
+*** TURN *** [Kd 7s 2c] [9h]
+Hero: bets $0.50
+Button Guy: folds
+Uncalled bet ($0.50) returned to Hero
+Hero collected $0.76 from pot
+Hero: doesn't show hand
+*** SUMMARY ***
+Total pot $0.77 | Rake $0.01
+Board [Kd 7s 2c 9h]
+Seat 1: Villain (small blind) folded before Flop
+Seat 2: Hero (big blind) collected ($0.76)
+Seat 4: Button Guy (button) folded on the Turn
+";
+    let recorded = parse_pokerstars(hand_history).unwrap();
+    assert_eq!(recorded.len(), 1);
+    let hand = &recorded[0];
+    assert_eq!(hand.hand_id, "245678901234");
+    assert_eq!(hand.seats.iter().map(|x| x.balance).collect_vec(), vec![212, 200, 150]);
+    assert_eq!((hand.button, hand.config.forced_bets.blinds), (2, Some((1, 2))));
+    assert_eq!(hand.hands[1], Some([Card(CardSuit::Hearts, CardValue::Ace), Card(CardSuit::Hearts, CardValue::King)]));
+    assert_eq!(hand.total_pot, Some(127));
+
+    // The engine takes no rake, so the winner ends up with the raked cent
+    let replay = hand.replay(StdRng::seed_from_u64(SEED)).unwrap();
+    assert!(matches!(replay.divergences.as_slice(), [Divergence::Stack { player_id, recorded: 238, replayed: 239 }] if player_id == "Hero"));
+    assert_eq!(replay.steps.len(), 8);
+}
+
+#[test]
+fn test_phh_replay() {
+    let phh = r#"
+variant = "NT"
+antes = [0, 0, 0]
+blinds_or_straddles = [1, 2, 0]
+min_bet = 2
+starting_stacks = [100, 100, 100]
+players = ["Alice", "Bob", "Carol"]
+actions = [
+  "d dh p1 AcAd",
+  "d dh p2 ????",
+  "d dh p3 7h2c",
+  "p3 f",
+  "p1 cbr 6",  # raise to 6
+  "p2 cc",
+  "d db KsQh3c",
+  "p1 cbr 10",
+  "p2 f",
+]
+finishing_stacks = [106, 94, 100]
+"#;
+    let recorded = parse_phh(phh).unwrap();
+    assert_eq!(recorded.button, 2);
+    assert_eq!(recorded.board.len(), 3);
+
+    let replay = recorded.replay(StdRng::seed_from_u64(SEED)).unwrap();
+    assert!(replay.divergences.is_empty(), "{}", replay.divergences.iter().join(", "));
+    assert_eq!(replay.steps.len(), 5);
+    assert_eq!(replay.steps[1].0.current_player.hand, [Card(CardSuit::Clubs, CardValue::Ace), Card(CardSuit::Diamonds, CardValue::Ace)]);
+
+    // A record the engine disagrees with is reported rather than followed
+    let wrong = parse_phh(&phh.replace("[106, 94, 100]", "[108, 92, 100]")).unwrap();
+    let divergences = wrong.replay(StdRng::seed_from_u64(SEED)).unwrap().divergences;
+    assert!(matches!(divergences.as_slice(), [Divergence::Stack { recorded: 108, replayed: 106, .. }, Divergence::Stack { .. }]));
+}
+
+#[test]
+fn test_exact_equity() {
+    let board = ["SK", "HQ", "D7", "C2"].map(card);
+    let rng = || StdRng::seed_from_u64(SEED);
+
+    // Kings beat aces on every river but the two aces left
+    let aces = Opponents::Ranges(vec!["AhAd".parse().unwrap()]);
+    let equity = calculate_equity([card("HK"), card("DK")], &board, &[], &aces, 1000, rng()).unwrap();
+    assert!(equity.exact);
+    assert_eq!(equity.samples, 44);
+    assert!((equity.win - 42.0 / 44.0).abs() < 1e-9);
+    assert!((equity.lose - 2.0 / 44.0).abs() < 1e-9);
+    assert_eq!(equity.tie, 0.0);
+
+    // Both sides of the same deals add up to the whole pot
+    let kings = Opponents::Ranges(vec!["KhKd".parse().unwrap()]);
+    let other_side = calculate_equity([card("HA"), card("DA")], &board, &[], &kings, 1000, rng()).unwrap();
+    assert!((equity.equity + other_side.equity - 1.0).abs() < 1e-9);
+
+    // Dead cards leave the deck, and ties split the pot
+    let equity = calculate_equity([card("C3"), card("C4")], &["SA", "SK", "SQ", "SJ", "SX"].map(card), &[], &Opponents::Random(1), 1000, rng()).unwrap();
+    assert_eq!((equity.tie, equity.equity), (1.0, 0.5));
+    let equity = calculate_equity([card("HK"), card("DK")], &board, &[card("SA"), card("CA")], &aces, 1000, rng()).unwrap();
+    assert_eq!((equity.win, equity.samples), (1.0, 42));
+
+    assert_eq!(calculate_equity([card("HK"), card("DK")], &board, &[card("HK")], &aces, 1000, rng()), Err(EquityError::DuplicateCard(card("HK"))));
+    assert_eq!(calculate_equity([card("HA"), card("DK")], &board, &[], &aces, 1000, rng()), Err(EquityError::NoPossibleDeal));
+    assert_eq!(calculate_equity([card("HA"), card("DK")], &board, &[], &Opponents::Random(0), 1000, rng()), Err(EquityError::NoOpponents));
+}
+
+#[test]
+fn test_sampled_equity() {
+    let aces = [card("HA"), card("SA")];
+    let sample = |seed| calculate_equity(aces, &[], &[], &Opponents::Random(1), 20_000, StdRng::seed_from_u64(seed)).unwrap();
+
+    // Aces win about 85% of the time against a random hand
+    let equity = sample(SEED);
+    assert!(!equity.exact);
+    assert_eq!(equity.samples, 20_000);
+    assert!((equity.equity - 0.852).abs() < 0.015, "{:?}", equity);
+    assert!((equity.win + equity.tie + equity.lose - 1.0).abs() < 1e-9);
+    assert_eq!(sample(SEED), equity);
+    assert_eq!(calculate_equity(aces, &[], &[], &Opponents::Random(1), 0, StdRng::seed_from_u64(SEED)), Err(EquityError::NoSamples));
+
+    // Weights count in both ways of working equity out
+    let range = Opponents::Ranges(vec!["AhAd:3, 7c2d".parse().unwrap()]);
+    let against = |hand: [&str; 2]| Opponents::Ranges(vec![Range::from_combos([(hand.map(card), 1.0)])]);
+    let kings = [card("SK"), card("CK")];
+    let board = [card("S9"), card("H8"), card("C3")];
+    let exact = calculate_equity(kings, &board, &[], &range, 0, StdRng::seed_from_u64(SEED)).unwrap();
+    let versus_aces = calculate_equity(kings, &board, &[], &against(["HA", "DA"]), 0, StdRng::seed_from_u64(SEED)).unwrap();
+    let versus_seven_two = calculate_equity(kings, &board, &[], &against(["C7", "D2"]), 0, StdRng::seed_from_u64(SEED)).unwrap();
+    assert!(exact.exact);
+    assert!((exact.equity - (3.0 * versus_aces.equity + versus_seven_two.equity) / 4.0).abs() < 1e-9);
+
+    // Kings have about 18% against aces and 87% against seven two before the flop
+    let sampled = calculate_equity(kings, &[], &[], &range, 20_000, StdRng::seed_from_u64(SEED)).unwrap();
+    assert!(!sampled.exact);
+    assert!((sampled.equity - (3.0 * 0.18 + 0.87) / 4.0).abs() < 0.02, "{:?}", sampled);
+}
+
+#[test]
+fn test_range_parsing() {
+    let combos = |text: &str| text.parse::<Range>().unwrap().len();
+
+    assert_eq!(combos("AA"), 6);
+    assert_eq!(combos("AKs"), 4);
+    assert_eq!(combos("AKo"), 12);
+    assert_eq!(combos("KA"), 16);
+    assert_eq!(combos("QQ+"), 18);
+    assert_eq!(combos("22-55"), 24);
+    assert_eq!(combos("55-22"), 24);
+    assert_eq!(combos("A2s+"), 48);
+    assert_eq!(combos("KTo-K8o"), 36);
+    assert_eq!(combos("AhKh, AKs"), 4);
+    assert_eq!(combos("QQ+, AKs, KQo, 22-55"), 18 + 4 + 12 + 24);
+    assert_eq!(combos(""), 0);
+    for text in ["AKx", "AAs", "A2s-K2s", "22-AKs", "AKs-AQo", "AhAh", "AK:-1", "AK:x", "Z2"] {
+        assert!(text.parse::<Range>().is_err(), "{} should not parse", text);
+    }
+
+    // Later parts set the weight of combinations given again, and a weight of nothing drops them
+    let range: Range = "AK, AKs:0.5, AhKd:0".parse().unwrap();
+    assert_eq!(range.len(), 15);
+    assert_eq!(range.combos().iter().filter(|x| x.1 == 0.5).count(), 4);
+    assert!(range.combos().iter().all(|([first, second], _)| first > second));
+
+    // Card removal
+    let range: Range = "AA, KK".parse().unwrap();
+    assert_eq!(range.without(&[card("HA")]).len(), 9);
+    assert_eq!(range.without(&[card("HA"), card("SA"), card("DA")]).len(), 6);
+}
+
+#[test]
+fn test_range_equity() {
+    let rng = || StdRng::seed_from_u64(SEED);
+    let board = ["SK", "HQ", "D7", "C2"].map(card);
+    let aces = Opponents::Ranges(vec!["AhAd".parse().unwrap()]);
+
+    // A range of one hand is the same as the hand
+    let hand = calculate_equity([card("HK"), card("DK")], &board, &[], &aces, 0, rng()).unwrap();
+    let range = calculate_range_equity(&"KhKd".parse().unwrap(), &board, &[], &aces, 0, rng()).unwrap();
+    assert_eq!(hand, range);
+
+    // Both sides of a range against range match up, with the board taking combinations out
+    let kings: Range = "KK".parse().unwrap();
+    let others: Range = "AA, QQ".parse().unwrap();
+    let hero = calculate_range_equity(&kings, &board, &[], &Opponents::Ranges(vec![others.clone()]), 0, rng()).unwrap();
+    let villain = calculate_range_equity(&others, &board, &[], &Opponents::Ranges(vec![kings]), 0, rng()).unwrap();
+    assert!(hero.exact);
+    assert!((hero.equity + villain.equity - 1.0).abs() < 1e-9);
+    assert!((hero.win - villain.lose).abs() < 1e-9);
+    assert!(hero.equity > 0.5);
+
+    let empty = calculate_range_equity(&"KK".parse().unwrap(), &["SK", "HK", "DK"].map(card), &[], &Opponents::Random(1), 0, rng());
+    assert_eq!(empty, Err(EquityError::NoPossibleDeal));
+}
+
+#[test]
+fn test_vec_table() {
+    let test_players = players(2, 100);
+    let forced_bets = ForcedBets { blinds: Some((1, 2)), ..ForcedBets::default() };
+    let kind = |kind: ActionKind| ActionKind::ALL.iter().position(|x| *x == kind).unwrap();
+
+    // A rejected action leaves the table untouched
+    let config = TableConfig { illegal_action_policy: IllegalActionPolicy::Reject, forced_bets, ..TableConfig::default() };
+    let table = crate::VecTable::new(SEED, test_players.clone(), 1, config.clone());
+    assert!(table.validate(kind(ActionKind::Bet), None).is_err());
+    assert_eq!(table.environment().unwrap().current_player.seat, 0);
+
+    // The button calls and every street is checked down, then the next hand is dealt with the button moved on
+    let mut table = crate::VecTable::new(SEED, test_players, 1, config);
+    let mut actions = vec![ActionKind::Call];
+    actions.extend([ActionKind::Check; 7]);
+    let mut results = Vec::new();
+    for action in actions {
+        let action = table.validate(kind(action), None).unwrap();
+        results.push(table.step(action));
+    }
+    assert!(results[..7].iter().all(Option::is_none));
+    let deltas = results[7].clone().expect("The hand should have finished on the river");
+    assert_eq!(deltas.iter().sum::<isize>(), 0);
+    assert!(deltas.iter().all(|x| [-2, 0, 2].contains(x)));
+
+    let environment = table.environment().expect("A new hand should have been dealt");
+    assert_eq!(environment.button, 1);
+    assert!(environment.table_cards.is_empty());
+    assert_eq!(environment.total_chips(), 200);
+
+    // A fold ends the hand straight away
+    let action = table.validate(kind(ActionKind::Fold), None).unwrap();
+    let deltas = table.step(action).unwrap();
+    assert_eq!(deltas[environment.current_player.seat], -1);
+    assert_eq!(table.environment().unwrap().button, 0);
+}
+
+#[test]
+fn test_encoded_environment() {
+    let test_players = players(3, 50);
+    let game = GameState::new_with_players(StdRng::seed_from_u64(SEED), test_players, 2, 0);
+    let GameState::BettingRound(betting_round) = game else { unreachable!() };
+    let environment = betting_round.get_environment();
+
+    // Raises go to a fraction of the pot within the legal amounts, or the minimum without one
+    assert_eq!(environment.action_of_kind(ActionKind::Raise, None), Action::RaiseTo(8));
+    assert_eq!(environment.action_of_kind(ActionKind::Raise, Some(2.0)), Action::RaiseTo(16));
+    assert_eq!(environment.action_of_kind(ActionKind::Raise, Some(100.0)), Action::RaiseTo(50));
+    assert_eq!(environment.action_of_kind(ActionKind::Bet, Some(1.0)), Action::Bet(0));
+    assert_eq!(environment.action_of_kind(ActionKind::Call, None), Action::Call);
+
+    // The button folds and the small blind raises, leaving the big blind to act
+    let game = betting_round.update_state(Action::Fold).unwrap();
+    let GameState::BettingRound(betting_round) = game else { unreachable!() };
+    let game = betting_round.update_state(Action::RaiseTo(12)).unwrap();
+    let GameState::BettingRound(betting_round) = game else { unreachable!() };
+    let environment = betting_round.get_environment();
+    assert_eq!(environment.current_player.seat, 2);
+    assert_eq!(environment.n_seats(), 3);
+    assert_eq!(environment.total_chips(), 150);
+
+    let observation = environment.encode();
+    assert_eq!(OBSERVATION_SIZE, 514);
+    assert_eq!(observation[..52].iter().sum::<f32>(), 2.0);
+    for card in environment.current_player.hand {
+        assert_eq!(observation[card.index()], 1.0);
+    }
+    assert_eq!(observation[52..104].iter().sum::<f32>(), 0.0);
+    assert_eq!(observation[104..108], [1.0, 0.0, 0.0, 0.0]);
+    // Two seats on from the button
+    assert_eq!(observation[110], 1.0);
+    assert_eq!(observation[108..118].iter().sum::<f32>(), 1.0);
+    // The player, then the button who folded, then the small blind who raised
+    assert_eq!(observation[118..133], [
+        1.0, 1.0, 0.0, 46.0 / 150.0, 4.0 / 150.0,
+        1.0, 0.0, 0.0, 50.0 / 150.0, 0.0,
+        1.0, 1.0, 0.0, 38.0 / 150.0, 12.0 / 150.0
+    ]);
+    assert_eq!(observation[133..168].iter().sum::<f32>(), 0.0);
+    assert_eq!(observation[168..171], [16.0 / 150.0, 8.0 / 150.0, 12.0 / 150.0]);
+    assert_eq!(observation[172..178], [1.0, 0.0, 1.0, 0.0, 1.0, 1.0]);
+    // The raise comes first, then the fold, without the blinds
+    let raise = &observation[178..199];
+    assert_eq!(raise[4], 1.0);
+    assert_eq!(raise[6], 1.0);
+    assert_eq!(raise[10 + 2], 1.0);
+    assert_eq!(raise[20], 12.0 / 150.0);
+    assert_eq!(raise.iter().sum::<f32>(), 3.0 + 12.0 / 150.0);
+    let fold = &observation[199..220];
+    assert_eq!([fold[0], fold[6], fold[10 + 1]], [1.0; 3]);
+    assert_eq!(observation[220..].iter().sum::<f32>(), 0.0);
+
+    // Raises by an amount show as the street total they reach, and antes count towards the chips in the hand
+    let test_players = players(3, 50);
+    let config = TableConfig { forced_bets: ForcedBets { ante: 5, ..ForcedBets::default() }, ..TableConfig::default() };
+    let game = GameState::new_with_deck(Deck::new_with_rng(StdRng::seed_from_u64(SEED)), test_players, 2, 0, config);
+    let GameState::BettingRound(betting_round) = game else { unreachable!() };
+    let GameState::BettingRound(betting_round) = betting_round.update_state(Action::Raise(6)).unwrap() else { unreachable!() };
+    let environment = betting_round.get_environment();
+    assert_eq!(environment.total_chips(), 150);
+    let observation = environment.encode();
+    assert_eq!(observation[168], 31.0 / 150.0);
+    assert_eq!(observation[178 + 4], 1.0);
+    assert_eq!(observation[178 + 20], 10.0 / 150.0);
+    assert!(observation.iter().all(|x| (0.0..=1.0).contains(x)));
+}
+
+#[test]
+fn test_info_set_keys() {
+    let deal = |hands: [&str; 2], board: [&str; 5]| {
+        let test_players = vec![
+            Player { player_id: "Button".to_string(), balance: 100 },
+            Player { player_id: "Big Blind".to_string(), balance: 100 }
+        ];
+        let hands = hands.map(|x| Some(parse_cards(x).unwrap().try_into().unwrap()));
+        let mut game = GameState::new_with_hands(StdRng::seed_from_u64(SEED), test_players, &hands, &board.map(card), 2, 0, TableConfig::default());
+        for action in [Action::RaiseTo(8), Action::Call, Action::Check] {
+            let GameState::BettingRound(betting_round) = game else { unreachable!() };
+            game = betting_round.update_state(action).unwrap();
+        }
+        let GameState::BettingRound(betting_round) = game else { unreachable!() };
+        betting_round
+    };
+
+    // The button acts first before the flop and last after it
+    let betting_round = deal(["KhAh", "7c2d"], ["HT", "SQ", "DJ", "C3", "S9"]);
+    let environment = betting_round.get_environment();
+    assert_eq!(environment.current_player.seat, 0);
+    assert_eq!(environment.info_set_key(false), "AhKh|QsJdTh|0|r8c/k");
+    assert_eq!(betting_round.info_set_key(0, false).unwrap(), environment.info_set_key(false));
+    assert_eq!(betting_round.info_set_key(1, false).unwrap(), "7c2d|QsJdTh|1|r8c/k");
+    assert_eq!(betting_round.info_set_key(2, false), None);
+
+    // Hands that only differ by suit share a key once suits are renamed
+    let swapped = deal(["KsAs", "7c2d"], ["ST", "HQ", "DJ", "C3", "H9"]).get_environment();
+    assert_ne!(swapped.info_set_key(false), environment.info_set_key(false));
+    assert_eq!(swapped.info_set_key(true), environment.info_set_key(true));
+    assert_eq!(swapped.info_set_hash(true), environment.info_set_hash(true));
+    let offsuit = deal(["KdAh", "7c2s"], ["HT", "SQ", "DJ", "C3", "S9"]).get_environment();
+    assert_ne!(offsuit.info_set_key(true), environment.info_set_key(true));
+
+    // The same wager gives the same key however it was entered
+    let key_after = |action: Action| {
+        let game = GameState::new_with_players(StdRng::seed_from_u64(SEED), players(2, 100), 2, 0);
+        let GameState::BettingRound(betting_round) = game else { unreachable!() };
+        let GameState::BettingRound(betting_round) = betting_round.update_state(action).unwrap() else { unreachable!() };
+        betting_round.get_environment().info_set_key(false)
+    };
+    assert_eq!(key_after(Action::Raise(4)), key_after(Action::RaiseTo(8)));
+    assert!(key_after(Action::RaiseTo(8)).ends_with("|r8"));
+    assert_eq!(key_after(Action::AllIn), key_after(Action::RaiseTo(100)));
+
+    assert_eq!(info_set_hash(""), 0xcbf29ce484222325);
+    assert_eq!(info_set_hash("a"), 0xaf63dc4c8601ec8c);
+}
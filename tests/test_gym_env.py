@@ -0,0 +1,32 @@
+from gymnasium.utils.env_checker import check_env
+
+from poker_environment import ACTION_MASK_KINDS
+from poker_environment.gym_env import PokerEnv
+
+
+def fold_policy(environment) -> str:
+    return "FOLD"
+
+
+def test_check_env():
+    check_env(PokerEnv())
+    check_env(PokerEnv(opponents=[fold_policy]))
+
+
+def test_walk_ends_the_episode_with_its_chip_delta():
+    env = PokerEnv(opponents=[fold_policy])
+
+    # Heads up the agent starts on the button, so posts the small blind and acts first
+    _, info = env.reset(seed=0)
+    assert not info["hand_finished"]
+    _, reward, terminated, _, _ = env.step(ACTION_MASK_KINDS.index("FOLD"))
+    assert terminated
+    assert reward == -1.0
+
+    # With the button moved on the opponent folds first, leaving the agent a walk
+    observation, info = env.reset()
+    assert info["hand_finished"]
+    assert not observation.any()
+    observation, reward, terminated, truncated, _ = env.step(ACTION_MASK_KINDS.index("CHECK"))
+    assert terminated and not truncated
+    assert reward == 1.0
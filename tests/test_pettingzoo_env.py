@@ -0,0 +1,45 @@
+from pettingzoo.test import api_test
+
+from poker_environment import ACTION_MASK_KINDS
+from poker_environment.pettingzoo_env import PokerAECEnv
+
+# Deals a hand where the all in has a winner rather than splitting the pot
+SEED = 0
+
+
+def test_api():
+    api_test(PokerAECEnv(n_players=3, stack=20), num_cycles=1000)
+
+
+def test_fold_and_bust_in_one_hand():
+    env = PokerAECEnv(n_players=3, stack=10)
+    env.reset(seed=SEED)
+
+    # The first agent folds and sits the hand out, while the other two get all in
+    folder = env.agent_selection
+    env.step(ACTION_MASK_KINDS.index("FOLD"))
+    assert env.agent_selection != folder
+    shover = env.agent_selection
+    env.step(ACTION_MASK_KINDS.index("ALLIN"))
+    caller = env.agent_selection
+    assert caller not in (folder, shover)
+    env.step(ACTION_MASK_KINDS.index("CALL"))
+    # Players who are all in still check through the flop, turn and river
+    for _ in range(6):
+        assert env.agent_selection in (shover, caller)
+        env.step(ACTION_MASK_KINDS.index("CHECK"))
+
+    # The loser of the all in is out of chips and terminated, and the folder keeps playing
+    busted = [agent for agent in (shover, caller) if env.terminations[agent]]
+    assert len(busted) == 1
+    assert not env.terminations[folder]
+    assert env.rewards[busted[0]] == -10.0
+    assert env.rewards[folder] == 0.0
+
+    # The busted agent takes its dead step and leaves, and the tournament goes on heads up
+    assert env.agent_selection == busted[0]
+    env.step(None)
+    assert busted[0] not in env.agents
+    assert len(env.agents) == 2
+    assert env.agent_selection in env.agents
+
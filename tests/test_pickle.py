@@ -0,0 +1,37 @@
+import copy
+import pickle
+
+from poker_environment import PyPokerGame, PyPokerPlayerInfo
+
+
+def new_game() -> PyPokerGame:
+    return PyPokerGame([PyPokerPlayerInfo(f"Player {i + 1}", 100) for i in range(3)], 1, 12)
+
+
+def test_pickle_round_trip():
+    game = new_game()
+    game.advance("CALL")
+
+    restored = pickle.loads(pickle.dumps(game))
+    assert restored.to_json() == game.to_json()
+
+    # The restored game keeps dealing the same cards as the original
+    while not game.is_finished():
+        game.advance("CALL")
+        restored.advance("CALL")
+    assert restored.to_json() == game.to_json()
+    assert pickle.loads(pickle.dumps(game)).get_chip_deltas() == game.get_chip_deltas()
+
+
+def test_copy():
+    game = new_game()
+    assert copy.deepcopy(game).to_json() == game.to_json()
+
+
+def test_state():
+    game = new_game()
+    other = new_game()
+    other.advance("FOLD")
+
+    other.__setstate__(game.__getstate__())
+    assert other.to_json() == game.to_json()